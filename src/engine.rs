@@ -1,112 +1,1456 @@
-use std::fs;
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    sync::{Arc, atomic::AtomicBool},
+    time::{Duration, Instant},
+};
 
 use crate::{
-    geometry::{hittable_list::HittableList, sphere::Sphere, triangle::Triangle},
+    geometry::{
+        bvh::{Bvh, SplitMethod},
+        constant_medium::ConstantMedium,
+        disk::Disk,
+        hittable::{Hittable, SharedHittable},
+        hittable_list::HittableList,
+        mesh::TriangleMesh,
+        plane::Plane,
+        quad::{Quad, box_quads},
+        quadric::{Capsule, Cone, Cylinder},
+        sphere::Sphere,
+        tagged::{Tag, Tagged},
+        torus::Torus,
+        transformed::Transformed,
+        triangle::Triangle,
+    },
     imaging::{
+        accumulation::AccumulationBuffer,
+        aov::Aov,
+        atmosphere::{Atmosphere, DensityProfile},
         camera::Camera,
         canvas::Canvas,
-        material::{Dielectric, Lambertian, MaterialKind, Metal},
-        renderer::Renderer,
+        denoise::Denoiser,
+        filter::Filter,
+        material::{
+            Dielectric, DiffuseLight, HenyeyGreenstein, Isotropic, Lambertian, MaterialKind, Metal,
+        },
+        renderer::{AdaptiveSampling, Integrator, Renderer},
+        sampler::SamplerType,
+        texture::{CheckerTexture, ImageTexture, NoiseTexture, TextureKind},
+        tonemap::ToneMap,
+    },
+    math::{aabb::Aabb, mat4::Mat4, utils, vec3::Vec3},
+    scene::{
+        AtmosphereConfig, AtmosphereType, Background, BoundsConfig, CameraConfig, FilterConfig,
+        MaterialConfig, ObjectConfig, SceneConfig, SceneError, SceneErrorKind, TextureConfig,
+        TextureSource, ToneMapConfig, TransformConfig, load_obj,
     },
-    scene::{Background, MaterialConfig, ObjectConfig, SceneConfig},
 };
 
+/// A loaded scene, ready to render.
+pub struct Scene {
+    pub world: HittableList,
+    pub camera: Camera,
+    pub width: u32,
+    pub height: u32,
+    pub background: Background,
+    pub tonemap: ToneMap,
+    pub sampler: SamplerType,
+    pub filter: Filter,
+    pub atmosphere: Option<Atmosphere>,
+}
+
+/// The images produced by [`Engine::render`].
+pub struct RenderOutput {
+    pub canvas: Canvas,
+    /// Number of samples taken for each pixel, in canvas order.
+    pub sample_counts: Vec<u32>,
+    /// The AOVs requested with [`Engine::with_aovs`], in that order.
+    pub aovs: Vec<(Aov, Canvas)>,
+    /// `canvas` after denoising, if enabled with [`Engine::with_denoiser`].
+    pub denoised: Option<Canvas>,
+}
+
+impl RenderOutput {
+    fn from_buffer(buffer: &AccumulationBuffer, aovs: &[Aov]) -> Self {
+        Self {
+            canvas: buffer.to_canvas(),
+            sample_counts: buffer.sample_counts(),
+            aovs: aovs
+                .iter()
+                .filter_map(|&aov| Some((aov, buffer.aov_canvas(aov)?)))
+                .collect(),
+            denoised: None,
+        }
+    }
+}
+
+/// Progressive rendering: passes of `pass_samples` samples per pixel are
+/// added until a limit is reached or the render is interrupted.
+///
+/// The stratified sampler lays its strata over `max_samples`. With only a
+/// time limit the total is unknown, so each pass is stratified on its own.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progressive {
+    pub pass_samples: u32,
+    /// Stop once every pixel has had this many samples.
+    pub max_samples: Option<u32>,
+    /// Stop before starting a pass that would likely end past this budget.
+    pub time_limit: Option<Duration>,
+}
+
 pub struct Engine {
     renderer: Renderer,
+    split_method: SplitMethod,
+    progressive: Option<Progressive>,
+    aovs: Vec<Aov>,
+    denoiser: Option<Denoiser>,
 }
 
 impl Engine {
     pub fn new(samples: u32, depth: u32) -> Self {
         Self {
             renderer: Renderer::new(samples, depth),
+            split_method: SplitMethod::default(),
+            progressive: None,
+            aovs: Vec::new(),
+            denoiser: None,
         }
     }
 
-    pub fn load_scene_from_file(
-        &self,
-        path: &str,
-        width: u32,
-    ) -> (HittableList, Camera, u32, u32, Background) {
-        let yaml_data = fs::read_to_string(path).expect("Unable to read scene file");
-        let config: SceneConfig = serde_yaml::from_str(&yaml_data).expect("Invalid scene YAML");
+    pub fn with_integrator(mut self, integrator: Integrator) -> Self {
+        self.renderer.integrator = integrator;
+        self
+    }
 
-        let mut world = HittableList::default();
+    /// Paths longer than `bounces` are terminated by Russian roulette.
+    pub fn with_roulette_depth(mut self, bounces: u32) -> Self {
+        self.renderer.roulette_depth = bounces;
+        self
+    }
 
-        for obj in config.objects {
-            match obj {
-                ObjectConfig::Sphere {
-                    center,
-                    radius,
-                    material,
-                } => {
-                    let mat = match material {
-                        MaterialConfig::Lambertian { albedo } => {
-                            MaterialKind::Lambertian(Lambertian::new(albedo))
-                        }
-                        MaterialConfig::Metal { albedo, fuzz } => {
-                            MaterialKind::Metal(Metal::new(albedo, fuzz))
-                        }
-                        MaterialConfig::Dielectric { index } => {
-                            MaterialKind::Dielectric(Dielectric::new(index))
-                        }
-                    };
+    /// Seeds scene loading (e.g. procedural textures) and every pixel sample.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.renderer.seed = seed;
+        self
+    }
 
-                    world.add(Sphere::new(center, radius, mat));
-                }
-                ObjectConfig::Triangle {
-                    v0,
-                    v1,
-                    v2,
-                    material,
-                } => {
-                    let mat = match material {
-                        MaterialConfig::Lambertian { albedo } => {
-                            MaterialKind::Lambertian(Lambertian::new(albedo))
-                        }
-                        MaterialConfig::Metal { albedo, fuzz } => {
-                            MaterialKind::Metal(Metal::new(albedo, fuzz))
-                        }
-                        MaterialConfig::Dielectric { index } => {
-                            MaterialKind::Dielectric(Dielectric::new(index))
-                        }
-                    };
+    /// Lets pixels stop before the sample count once they are converged.
+    pub fn with_adaptive_sampling(mut self, adaptive: AdaptiveSampling) -> Self {
+        self.renderer.adaptive = Some(adaptive);
+        self
+    }
+
+    /// Renders in passes until a sample or time limit; the sample count
+    /// given to [`Engine::new`] is then ignored.
+    pub fn with_progressive(mut self, progressive: Progressive) -> Self {
+        self.progressive = Some(progressive);
+        self
+    }
+
+    /// Also renders the given AOVs, returned in [`RenderOutput::aovs`].
+    pub fn with_aovs(mut self, aovs: Vec<Aov>) -> Self {
+        self.aovs = aovs;
+        self
+    }
+
+    /// Denoises the final image, guided by its normal and albedo AOVs.
+    pub fn with_denoiser(mut self, denoiser: Denoiser) -> Self {
+        self.denoiser = Some(denoiser);
+        self
+    }
+
+    /// Stops rendering early, keeping the samples taken so far, once
+    /// `flag` is set.
+    pub fn with_interrupt(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.renderer.interrupt = Some(flag);
+        self
+    }
+
+    /// Selects how the acceleration structure is built before rendering.
+    pub fn with_split_method(mut self, method: SplitMethod) -> Self {
+        self.split_method = method;
+        self
+    }
 
-                    world.add(Triangle::new(v0, v1, v2, mat));
+    /// Loads a YAML scene, reporting unreadable, malformed or invalid
+    /// scenes as a [`SceneError`].
+    pub fn load_scene_from_file(&self, path: &str, width: u32) -> Result<Scene, SceneError> {
+        let scene_path = Path::new(path);
+        let error = |kind| SceneError::new(scene_path, kind);
+
+        let yaml_data = fs::read_to_string(path).map_err(|err| error(SceneErrorKind::Io(err)))?;
+        let config: SceneConfig = serde_yaml::from_str(&yaml_data)
+            .map_err(|err| SceneError::from_yaml(scene_path, err))?;
+
+        if config.aspect_ratio <= 0.0 {
+            return Err(error(invalid(format!(
+                "aspect_ratio must be positive, got {}",
+                config.aspect_ratio
+            ))));
+        }
+        validate_camera(&config.camera).map_err(|kind| error(kind).with_context("camera"))?;
+        let tonemap =
+            build_tonemap(&config.tonemap).map_err(|kind| error(kind).with_context("tonemap"))?;
+        let filter =
+            build_filter(&config.filter).map_err(|kind| error(kind).with_context("filter"))?;
+
+        let atmosphere = config
+            .atmosphere
+            .as_ref()
+            .map(build_atmosphere)
+            .transpose()
+            .map_err(|kind| error(kind).with_context("atmosphere"))?;
+
+        let base_dir = scene_path.parent().unwrap_or(Path::new(""));
+        utils::reseed(self.renderer.seed);
+
+        let mut materials = MaterialIds::default();
+        let mut definitions = HashMap::new();
+        for (name, objects) in config.definitions {
+            let mut group = HittableList::default();
+            for (i, obj) in objects.into_iter().enumerate() {
+                let context = format!("definitions.{name}[{i}]");
+                if matches!(obj, ObjectConfig::Instance { .. }) {
+                    return Err(
+                        error(invalid("definitions cannot contain instances".into()))
+                            .with_context(context),
+                    );
                 }
+                // Instances of the group carry the object ID.
+                add_object(&mut group, obj, 0, base_dir, &definitions, &mut materials)
+                    .map_err(|kind| error(kind).with_context(context))?;
             }
+            let group: SharedHittable = Arc::new(Bvh::with_split_method(&group, self.split_method));
+            definitions.insert(name, group);
+        }
+
+        let mut world = HittableList::default();
+        for (i, obj) in config.objects.into_iter().enumerate() {
+            let object_id = i as u32 + 1;
+            add_object(
+                &mut world,
+                obj,
+                object_id,
+                base_dir,
+                &definitions,
+                &mut materials,
+            )
+            .map_err(|kind| error(kind).with_context(format!("objects[{i}]")))?;
         }
 
-        let camera = Camera::builder()
+        let mut camera_builder = Camera::builder()
             .aspect_ratio(config.aspect_ratio)
             .look_from(config.camera.look_from)
             .look_at(config.camera.look_at)
             .vup(config.camera.vup)
             .fov(config.camera.fov)
             .defocus_angle(config.camera.defocus_angle)
-            .focus_dist(config.camera.focus_dist)
-            .build();
+            .focus_dist(config.camera.focus_dist);
+
+        if config.camera.auto_frame {
+            camera_builder = camera_builder.frame(&world.bounded_box());
+        }
+
+        let camera = camera_builder.build();
+
+        // Homogeneous fog never thins out, so unless told otherwise it fills
+        // the box around the bounded objects and the camera, and rays
+        // leaving that box still see the background.
+        let fit_to_scene = config.atmosphere.as_ref().is_some_and(|atmosphere| {
+            atmosphere.kind == AtmosphereType::Homogeneous && atmosphere.bounds.is_none()
+        });
+        let bounded = world.bounded_box();
+        let atmosphere = match atmosphere {
+            Some(atmosphere) if fit_to_scene && !bounded.is_empty() => {
+                let center = camera.center();
+                Some(atmosphere.with_bounds(Aabb::surrounding(
+                    bounded,
+                    Aabb::from_points(center, center),
+                )))
+            }
+            atmosphere => atmosphere,
+        };
 
         let height = (width as f64 / config.aspect_ratio) as u32;
 
-        (world, camera, width, height, config.background)
+        Ok(Scene {
+            world,
+            camera,
+            width,
+            height,
+            background: config.background,
+            tonemap,
+            sampler: config.sampler,
+            filter,
+            atmosphere,
+        })
+    }
+
+    pub fn render(&self, scene: &Scene) -> RenderOutput {
+        self.render_with_progress(scene, |_| {})
     }
 
-    pub fn render(
+    /// Renders `scene`, calling `on_pass` with the image so far after each
+    /// completed progressive pass.
+    pub fn render_with_progress(
         &self,
-        world: &HittableList,
-        camera: &Camera,
-        width: u32,
-        height: u32,
-        background: &Background,
-    ) -> Canvas {
-        let mut canvas = Canvas::new(width, width as f64 / height as f64);
-
-        println!(
-            "Rendering {}x{} image with {} samples...",
-            width, height, self.renderer.samples_per_pixel
+        scene: &Scene,
+        mut on_pass: impl FnMut(&RenderOutput),
+    ) -> RenderOutput {
+        let lights = scene.world.lights();
+        let bvh = Bvh::with_split_method(&scene.world, self.split_method);
+        let samples_per_pixel = match self.progressive {
+            Some(progressive) => progressive.max_samples.unwrap_or(progressive.pass_samples),
+            None => self.renderer.samples_per_pixel,
+        };
+        let renderer = Renderer {
+            samples_per_pixel,
+            sampler: scene.sampler,
+            filter: scene.filter,
+            atmosphere: scene.atmosphere.clone(),
+            ..self.renderer.clone()
+        };
+        let mut buffer = AccumulationBuffer::new(scene.width, scene.height);
+        if !self.aovs.is_empty() || self.denoiser.is_some() {
+            buffer = buffer.with_aovs();
+        }
+        let render_pass = |buffer: &mut AccumulationBuffer, samples| {
+            renderer.render_pass(
+                &bvh,
+                &lights,
+                &scene.camera,
+                &scene.background,
+                buffer,
+                samples,
+            )
+        };
+
+        match self.progressive {
+            None => {
+                println!(
+                    "Rendering {}x{} image with {} samples...",
+                    scene.width, scene.height, self.renderer.samples_per_pixel
+                );
+                render_pass(&mut buffer, self.renderer.samples_per_pixel);
+            }
+            Some(progressive) => {
+                println!(
+                    "Rendering {}x{} image progressively, {} samples per pass...",
+                    scene.width, scene.height, progressive.pass_samples
+                );
+
+                let start = Instant::now();
+                let mut total = 0u32;
+                for pass in 1.. {
+                    let samples = match progressive.max_samples {
+                        Some(max) => progressive.pass_samples.min(max - total),
+                        None => progressive.pass_samples,
+                    };
+                    if samples == 0 {
+                        break;
+                    }
+
+                    let pass_start = Instant::now();
+                    let taken = render_pass(&mut buffer, samples);
+                    if renderer.is_interrupted() {
+                        break;
+                    }
+                    total += samples;
+
+                    let elapsed = start.elapsed();
+                    println!(
+                        "Pass {pass}: {total} samples per pixel after {:.1}s",
+                        elapsed.as_secs_f64()
+                    );
+                    on_pass(&RenderOutput::from_buffer(&buffer, &self.aovs));
+
+                    if taken == 0 {
+                        break;
+                    }
+                    if let Some(limit) = progressive.time_limit
+                        && elapsed + pass_start.elapsed() > limit
+                    {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if renderer.is_interrupted() {
+            println!("Interrupted; keeping the samples taken so far.");
+        }
+        if self.renderer.adaptive.is_some() {
+            println!(
+                "Adaptive sampling: {:.1} samples per pixel on average",
+                buffer.total_samples() as f64 / buffer.pixels().len() as f64
+            );
+        }
+        let mut output = RenderOutput::from_buffer(&buffer, &self.aovs);
+        if let Some(denoiser) = &self.denoiser
+            && let (Some(normal), Some(albedo)) = (
+                buffer.aov_canvas(Aov::Normal),
+                buffer.aov_canvas(Aov::Albedo),
+            )
+        {
+            println!("Denoising...");
+            output.denoised = Some(denoiser.denoise(&output.canvas, &normal, &albedo));
+        }
+        output
+    }
+}
+
+fn invalid(message: String) -> SceneErrorKind {
+    SceneErrorKind::Invalid(message)
+}
+
+fn validate_camera(camera: &CameraConfig) -> Result<(), SceneErrorKind> {
+    let view = camera.look_at - camera.look_from;
+
+    if camera.fov <= 0.0 || camera.fov >= 180.0 {
+        return Err(invalid(format!(
+            "fov must be between 0 and 180 degrees, got {}",
+            camera.fov
+        )));
+    }
+    if camera.vup.near_zero() {
+        return Err(invalid("vup must not be a zero-length vector".into()));
+    }
+    if view.near_zero() {
+        return Err(invalid("look_from and look_at must differ".into()));
+    }
+    if view.cross(camera.vup).near_zero() {
+        return Err(invalid(
+            "vup must not be parallel to the viewing direction".into(),
+        ));
+    }
+    if camera.defocus_angle < 0.0 || camera.focus_dist <= 0.0 {
+        return Err(invalid(
+            "defocus_angle must not be negative and focus_dist must be positive".into(),
+        ));
+    }
+    Ok(())
+}
+
+fn build_tonemap(config: &ToneMapConfig) -> Result<ToneMap, SceneErrorKind> {
+    if !config.exposure.is_finite() {
+        return Err(invalid(format!(
+            "exposure must be a finite number of stops, got {}",
+            config.exposure
+        )));
+    }
+    if let Some(white) = config
+        .white_point
+        .filter(|&white| !(white > 0.0 && white.is_finite()))
+    {
+        return Err(invalid(format!(
+            "white_point must be a positive number, got {white}"
+        )));
+    }
+
+    Ok(ToneMap {
+        operator: config.operator,
+        exposure: config.exposure,
+        white_point: config.white_point,
+    })
+}
+
+fn build_filter(config: &FilterConfig) -> Result<Filter, SceneErrorKind> {
+    let mut filter = Filter::new(config.kind);
+    if let Some(radius) = config.radius {
+        if !(radius >= Filter::MIN_RADIUS && radius.is_finite()) {
+            return Err(invalid(format!(
+                "filter radius must be at least {}, got {radius}",
+                Filter::MIN_RADIUS
+            )));
+        }
+        filter.radius = radius;
+    }
+    Ok(filter)
+}
+
+fn build_atmosphere(config: &AtmosphereConfig) -> Result<Atmosphere, SceneErrorKind> {
+    if !(config.density > 0.0 && config.density.is_finite()) {
+        return Err(invalid(format!(
+            "atmosphere density must be positive, got {}",
+            config.density
+        )));
+    }
+    if !(config.g > -1.0 && config.g < 1.0) {
+        return Err(invalid(format!(
+            "atmosphere g must be between -1 and 1, got {}",
+            config.g
+        )));
+    }
+
+    let profile = match config.kind {
+        AtmosphereType::Homogeneous => {
+            if config.falloff.is_some() || config.base_height.is_some() {
+                return Err(invalid(
+                    "falloff and base_height only apply to HeightFog".into(),
+                ));
+            }
+            DensityProfile::Homogeneous
+        }
+        AtmosphereType::HeightFog => {
+            let falloff = config
+                .falloff
+                .ok_or_else(|| invalid("HeightFog needs a falloff".into()))?;
+            if !(falloff > 0.0 && falloff.is_finite()) {
+                return Err(invalid(format!(
+                    "atmosphere falloff must be positive, got {falloff}"
+                )));
+            }
+            DensityProfile::Exponential {
+                base_height: config.base_height.unwrap_or(0.0),
+                falloff,
+            }
+        }
+    };
+
+    let phase = if config.g == 0.0 {
+        MaterialKind::Isotropic(Isotropic::new(config.albedo))
+    } else {
+        MaterialKind::HenyeyGreenstein(HenyeyGreenstein::new(config.albedo, config.g))
+    };
+    let atmosphere = Atmosphere::new(config.density, profile, phase);
+    match &config.bounds {
+        Some(BoundsConfig { min, max }) if !(min.x < max.x && min.y < max.y && min.z < max.z) => {
+            Err(invalid(format!(
+                "atmosphere bounds need min below max on every axis, got {min} and {max}"
+            )))
+        }
+        Some(BoundsConfig { min, max }) => {
+            Ok(atmosphere.with_bounds(Aabb::from_points(*min, *max)))
+        }
+        None => Ok(atmosphere),
+    }
+}
+
+/// Adds the parts of `obj` to `list`, each tagged with `object_id` and the
+/// ID of its material.
+fn add_object(
+    list: &mut HittableList,
+    obj: ObjectConfig,
+    object_id: u32,
+    base_dir: &Path,
+    definitions: &HashMap<String, SharedHittable>,
+    materials: &mut MaterialIds,
+) -> Result<(), SceneErrorKind> {
+    match obj {
+        ObjectConfig::Sphere {
+            center,
+            radius,
+            material,
+            transform,
+        } => {
+            if radius <= 0.0 {
+                return Err(invalid(format!(
+                    "sphere radius must be positive, got {radius}"
+                )));
+            }
+            let tag = materials.tag(object_id, &material);
+            let sphere = Sphere::new(center, radius, build_material(material, base_dir)?);
+            add_transformed(list, Tagged::new(sphere, tag), transform)
+        }
+        ObjectConfig::Triangle {
+            v0,
+            v1,
+            v2,
+            n0,
+            n1,
+            n2,
+            material,
+            transform,
+        } => {
+            if (v1 - v0).cross(v2 - v0).near_zero() {
+                return Err(invalid(
+                    "triangle is degenerate (its vertices are collinear)".into(),
+                ));
+            }
+            let tag = materials.tag(object_id, &material);
+            let triangle = Triangle::new(v0, v1, v2, build_material(material, base_dir)?);
+
+            match (n0, n1, n2) {
+                (Some(n0), Some(n1), Some(n2)) => add_transformed(
+                    list,
+                    Tagged::new(triangle.with_normals(n0, n1, n2), tag),
+                    transform,
+                ),
+                (None, None, None) => add_transformed(list, Tagged::new(triangle, tag), transform),
+                _ => Err(invalid("triangle normals need all of n0, n1 and n2".into())),
+            }
+        }
+        ObjectConfig::Quad {
+            corner,
+            u,
+            v,
+            material,
+            transform,
+        } => {
+            if u.cross(v).near_zero() {
+                return Err(invalid(
+                    "quad is degenerate (its edges are parallel or zero)".into(),
+                ));
+            }
+            let tag = materials.tag(object_id, &material);
+            let quad = Quad::new(corner, u, v, build_material(material, base_dir)?);
+            add_transformed(list, Tagged::new(quad, tag), transform)
+        }
+        ObjectConfig::Plane {
+            point,
+            normal,
+            texture_scale,
+            material,
+            transform,
+        } => {
+            if normal.near_zero() {
+                return Err(invalid("plane normal must not be zero".into()));
+            }
+            if texture_scale <= 0.0 {
+                return Err(invalid(format!(
+                    "plane texture_scale must be positive, got {texture_scale}"
+                )));
+            }
+            let tag = materials.tag(object_id, &material);
+            let plane = Plane::new(point, normal, build_material(material, base_dir)?)
+                .with_texture_scale(texture_scale);
+            add_transformed(list, Tagged::new(plane, tag), transform)
+        }
+        ObjectConfig::Disk {
+            center,
+            normal,
+            radius,
+            material,
+            transform,
+        } => {
+            if normal.near_zero() {
+                return Err(invalid("disk normal must not be zero".into()));
+            }
+            if radius <= 0.0 {
+                return Err(invalid(format!(
+                    "disk radius must be positive, got {radius}"
+                )));
+            }
+            let tag = materials.tag(object_id, &material);
+            let disk = Disk::new(center, normal, radius, build_material(material, base_dir)?);
+            add_transformed(list, Tagged::new(disk, tag), transform)
+        }
+        ObjectConfig::Cylinder {
+            base,
+            axis,
+            radius,
+            caps,
+            material,
+            transform,
+        } => {
+            validate_axis(axis, radius)?;
+            let tag = materials.tag(object_id, &material);
+            let cylinder = Cylinder::new(base, axis, radius, build_material(material, base_dir)?)
+                .with_caps(caps);
+            add_transformed(list, Tagged::new(cylinder, tag), transform)
+        }
+        ObjectConfig::Cone {
+            base,
+            axis,
+            radius,
+            top_radius,
+            caps,
+            material,
+            transform,
+        } => {
+            validate_axis(axis, radius)?;
+            if top_radius < 0.0 {
+                return Err(invalid(format!(
+                    "cone top_radius must not be negative, got {top_radius}"
+                )));
+            }
+            let tag = materials.tag(object_id, &material);
+            let cone = Cone::new(
+                base,
+                axis,
+                radius,
+                top_radius,
+                build_material(material, base_dir)?,
+            )
+            .with_caps(caps);
+            add_transformed(list, Tagged::new(cone, tag), transform)
+        }
+        ObjectConfig::Capsule {
+            base,
+            axis,
+            radius,
+            material,
+            transform,
+        } => {
+            validate_axis(axis, radius)?;
+            let tag = materials.tag(object_id, &material);
+            let capsule = Capsule::new(base, axis, radius, build_material(material, base_dir)?);
+            add_transformed(list, Tagged::new(capsule, tag), transform)
+        }
+        ObjectConfig::Torus {
+            center,
+            axis,
+            major_radius,
+            minor_radius,
+            material,
+            transform,
+        } => {
+            if axis.near_zero() {
+                return Err(invalid("torus axis must not be zero".into()));
+            }
+            if !(minor_radius > 0.0 && minor_radius <= major_radius) {
+                return Err(invalid(format!(
+                    "torus radii must satisfy 0 < minor_radius <= major_radius, \
+                     got minor {minor_radius} and major {major_radius}"
+                )));
+            }
+            let tag = materials.tag(object_id, &material);
+            let torus = Torus::new(
+                center,
+                axis,
+                major_radius,
+                minor_radius,
+                build_material(material, base_dir)?,
+            );
+            add_transformed(list, Tagged::new(torus, tag), transform)
+        }
+        ObjectConfig::Box {
+            min,
+            max,
+            material,
+            transform,
+        } => {
+            if !(min.x < max.x && min.y < max.y && min.z < max.z) {
+                return Err(invalid(
+                    "box max must be greater than min on every axis".into(),
+                ));
+            }
+            let tag = materials.tag(object_id, &material);
+            let faces = box_quads(min, max, build_material(material, base_dir)?);
+            match transform {
+                Some(_) => {
+                    let mut sides = HittableList::default();
+                    for face in faces {
+                        sides.add(face);
+                    }
+                    add_transformed(list, Tagged::new(sides, tag), transform)
+                }
+                None => {
+                    for face in faces {
+                        list.add(Tagged::new(face, tag));
+                    }
+                    Ok(())
+                }
+            }
+        }
+        ObjectConfig::Mesh {
+            path,
+            material,
+            transform,
+        } => {
+            let mesh_path = base_dir.join(&path);
+            let tag = materials.tag(object_id, &material);
+            let mut mesh =
+                load_obj(&mesh_path, build_material(material, base_dir)?).map_err(|source| {
+                    SceneErrorKind::Mesh {
+                        path: mesh_path,
+                        source: Box::new(source),
+                    }
+                })?;
+
+            // Baking is cheaper to trace than wrapping every face.
+            if let Some(transform) = transform {
+                bake_transform(&mut mesh, &transform_matrix(&transform))?;
+            }
+
+            let mesh = Arc::new(mesh);
+            for triangle in TriangleMesh::triangles(&mesh) {
+                list.add(Tagged::new(triangle, tag));
+            }
+            Ok(())
+        }
+        ObjectConfig::Volume {
+            boundary,
+            density,
+            material,
+        } => {
+            if !(density > 0.0 && density.is_finite()) {
+                return Err(invalid(format!(
+                    "volume density must be positive, got {density}"
+                )));
+            }
+            if !matches!(
+                material,
+                MaterialConfig::Isotropic { .. } | MaterialConfig::HenyeyGreenstein { .. }
+            ) {
+                return Err(invalid(
+                    "volume material must be a phase function (Isotropic or HenyeyGreenstein)"
+                        .into(),
+                ));
+            }
+            if matches!(*boundary, ObjectConfig::Volume { .. }) {
+                return Err(invalid("volume boundary cannot be another volume".into()));
+            }
+
+            // The boundary's material is never seen, so it takes no ID.
+            let mut parts = HittableList::default();
+            add_object(
+                &mut parts,
+                *boundary,
+                0,
+                base_dir,
+                definitions,
+                &mut MaterialIds::default(),
+            )?;
+
+            let tag = materials.tag(object_id, &material);
+            let medium = ConstantMedium::new(
+                Arc::new(parts),
+                density,
+                build_material(material, base_dir)?,
+            );
+            list.add(Tagged::new(medium, tag));
+            Ok(())
+        }
+        ObjectConfig::Instance { of, transform } => {
+            let definition = definitions
+                .get(&of)
+                .ok_or_else(|| invalid(format!("unknown definition '{of}'")))?
+                .clone();
+            // The definition's parts already carry their material IDs.
+            add_transformed(
+                list,
+                Tagged::new(definition, Tag::new(object_id, 0)),
+                transform,
+            )
+        }
+    }
+}
+
+/// Checks the shared parameters of shapes built around an axis.
+fn validate_axis(axis: Vec3, radius: f64) -> Result<(), SceneErrorKind> {
+    if axis.near_zero() {
+        return Err(invalid("axis must not be zero".into()));
+    }
+    if radius <= 0.0 {
+        return Err(invalid(format!("radius must be positive, got {radius}")));
+    }
+    Ok(())
+}
+
+/// Numbers the distinct materials of a scene, for the material ID AOV.
+#[derive(Default)]
+struct MaterialIds {
+    materials: Vec<MaterialConfig>,
+}
+
+impl MaterialIds {
+    /// Returns the tag for a part of object `object_id` made of `material`,
+    /// whose ID is shared by all identical materials.
+    fn tag(&mut self, object_id: u32, material: &MaterialConfig) -> Tag {
+        let index = match self.materials.iter().position(|m| m == material) {
+            Some(index) => index,
+            None => {
+                self.materials.push(material.clone());
+                self.materials.len() - 1
+            }
+        };
+        Tag::new(object_id, index as u32 + 1)
+    }
+}
+
+/// Adds `object`, wrapped in a [`Transformed`] if the config asks for one.
+fn add_transformed<H: Hittable + Send + Sync + 'static>(
+    list: &mut HittableList,
+    object: H,
+    transform: Option<TransformConfig>,
+) -> Result<(), SceneErrorKind> {
+    match transform {
+        Some(transform) => list.add(
+            Transformed::new(object, transform_matrix(&transform)).ok_or_else(not_invertible)?,
+        ),
+        None => list.add(object),
+    }
+    Ok(())
+}
+
+fn not_invertible() -> SceneErrorKind {
+    invalid("transform is not invertible (is a scale factor zero?)".into())
+}
+
+/// Composes scale, then rotation about x, y and z, then translation.
+fn transform_matrix(transform: &TransformConfig) -> Mat4 {
+    Mat4::translation(transform.translate)
+        * Mat4::rotation_z(transform.rotate.z)
+        * Mat4::rotation_y(transform.rotate.y)
+        * Mat4::rotation_x(transform.rotate.x)
+        * Mat4::scaling(transform.scale.to_vec3())
+}
+
+fn build_material(config: MaterialConfig, base_dir: &Path) -> Result<MaterialKind, SceneErrorKind> {
+    Ok(match config {
+        MaterialConfig::Lambertian { albedo } => {
+            MaterialKind::Lambertian(Lambertian::from_texture(build_texture(albedo, base_dir)?))
+        }
+        MaterialConfig::Metal { albedo, fuzz } => {
+            MaterialKind::Metal(Metal::from_texture(build_texture(albedo, base_dir)?, fuzz))
+        }
+        MaterialConfig::Dielectric { index } => {
+            if index <= 0.0 {
+                return Err(invalid(format!(
+                    "refraction index must be positive, got {index}"
+                )));
+            }
+            MaterialKind::Dielectric(Dielectric::new(index))
+        }
+        MaterialConfig::DiffuseLight { emit } => {
+            MaterialKind::DiffuseLight(DiffuseLight::from_texture(build_texture(emit, base_dir)?))
+        }
+        MaterialConfig::Isotropic { albedo } => {
+            MaterialKind::Isotropic(Isotropic::from_texture(build_texture(albedo, base_dir)?))
+        }
+        MaterialConfig::HenyeyGreenstein { albedo, g } => {
+            if !(g > -1.0 && g < 1.0) {
+                return Err(invalid(format!(
+                    "Henyey-Greenstein g must be between -1 and 1, got {g}"
+                )));
+            }
+            MaterialKind::HenyeyGreenstein(HenyeyGreenstein::from_texture(
+                build_texture(albedo, base_dir)?,
+                g,
+            ))
+        }
+    })
+}
+
+fn build_texture(source: TextureSource, base_dir: &Path) -> Result<TextureKind, SceneErrorKind> {
+    let config = match source {
+        TextureSource::Color(color) => return Ok(color.into()),
+        TextureSource::Texture(config) => config,
+    };
+
+    Ok(match config {
+        TextureConfig::Solid { color } => color.into(),
+        TextureConfig::Checker { scale, even, odd } => TextureKind::Checker(CheckerTexture::new(
+            scale,
+            build_texture(*even, base_dir)?,
+            build_texture(*odd, base_dir)?,
+        )),
+        TextureConfig::Image { path } => {
+            let path = base_dir.join(path);
+            let texture = ImageTexture::load(&path).map_err(|source| SceneErrorKind::Texture {
+                path,
+                source: Box::new(source),
+            })?;
+            TextureKind::Image(texture)
+        }
+        TextureConfig::Noise { scale, color } => {
+            TextureKind::Noise(NoiseTexture::new(scale, color))
+        }
+    })
+}
+
+/// Moves mesh vertices (and normals) into world space once at load time.
+fn bake_transform(mesh: &mut TriangleMesh, transform: &Mat4) -> Result<(), SceneErrorKind> {
+    let normal_matrix = transform.inverse().ok_or_else(not_invertible)?.transpose();
+
+    for p in &mut mesh.positions {
+        *p = transform.transform_point(*p);
+    }
+    for n in &mut mesh.normals {
+        *n = normal_matrix.transform_vector(*n).unit_vector();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        imaging::{filter::FilterType, tonemap::ToneMapOperator},
+        math::{interval::Interval, ray::Ray, vec3::Vec3},
+        scene::Location,
+    };
+
+    const CAMERA: &str = "aspect_ratio: 1.5
+camera:
+  look_from: { x: 0.0, y: 0.0, z: 1.0 }
+  look_at: { x: 0.0, y: 0.0, z: 0.0 }
+  vup: { x: 0.0, y: 1.0, z: 0.0 }
+  fov: 40.0
+  defocus_angle: 0.0
+  focus_dist: 1.0
+";
+
+    fn load_scene(name: &str, yaml: &str) -> Result<Scene, SceneError> {
+        let path = std::env::temp_dir().join(format!("rust-rt-{}-{name}.yaml", std::process::id()));
+        fs::write(&path, yaml).unwrap();
+        let result = Engine::new(1, 1).load_scene_from_file(path.to_str().unwrap(), 10);
+        fs::remove_file(&path).unwrap();
+        result
+    }
+
+    fn load(name: &str, yaml: &str) -> Result<(), SceneError> {
+        load_scene(name, yaml).map(|_| ())
+    }
+
+    fn invalid_message(err: SceneError) -> String {
+        match err.kind {
+            SceneErrorKind::Invalid(message) => message,
+            other => panic!("expected a semantic error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_valid_scene_loads() {
+        let yaml = format!(
+            "{CAMERA}objects:
+  - type: Sphere
+    center: {{ x: 0.0, y: 0.0, z: 0.0 }}
+    radius: 1.0
+    material: {{ type: Lambertian, albedo: {{ x: 0.5, y: 0.5, z: 0.5 }} }}
+"
+        );
+        assert!(load("valid", &yaml).is_ok());
+    }
+
+    #[test]
+    fn test_texture_source_is_a_color_or_a_texture() {
+        let parse = |yaml| serde_yaml::from_str::<TextureSource>(yaml).unwrap();
+        assert!(matches!(
+            parse("{ x: 0.1, y: 0.2, z: 0.3 }"),
+            TextureSource::Color(color) if color == Vec3::new(0.1, 0.2, 0.3)
+        ));
+        assert!(matches!(
+            parse("{ type: Noise, scale: 2.0 }"),
+            TextureSource::Texture(TextureConfig::Noise { scale, color })
+                if scale == 2.0 && color == Vec3::new(1.0, 1.0, 1.0)
+        ));
+        let Ok(TextureSource::Texture(TextureConfig::Checker { even, odd, .. })) =
+            serde_yaml::from_str(
+                "{ type: Checker, scale: 1.0, even: { x: 1.0, y: 1.0, z: 1.0 },
+                   odd: { type: Solid, color: { x: 0.0, y: 0.0, z: 0.0 } } }",
+            )
+        else {
+            panic!("checker failed to parse");
+        };
+        assert!(matches!(*even, TextureSource::Color(_)));
+        assert!(matches!(
+            *odd,
+            TextureSource::Texture(TextureConfig::Solid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_diffuse_light_is_a_light() {
+        let yaml = format!(
+            "{CAMERA}objects:
+  - type: Sphere
+    center: {{ x: 0.0, y: 0.0, z: 0.0 }}
+    radius: 1.0
+    material: {{ type: DiffuseLight, emit: {{ x: 4.0, y: 2.0, z: 1.0 }} }}
+"
+        );
+        let Ok(scene) = load_scene("light", &yaml) else {
+            panic!("scene failed to load");
+        };
+        assert_eq!(scene.world.lights().objects().len(), 1);
+
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let rec = scene
+            .world
+            .hit(&ray, Interval::new(0.001, f64::INFINITY))
+            .unwrap();
+        assert_eq!(rec.material.emitted(&rec), Vec3::new(4.0, 2.0, 1.0));
+    }
+
+    #[test]
+    fn test_objects_and_materials_are_numbered() {
+        let sphere = |x: f64, gray: f64| {
+            format!(
+                "  - type: Sphere
+    center: {{ x: {x}, y: 0.0, z: 0.0 }}
+    radius: 0.5
+    material: {{ type: Lambertian, albedo: {{ x: {gray}, y: {gray}, z: {gray} }} }}
+"
+            )
+        };
+        // Definitions are numbered first, and instances are objects too.
+        let yaml = format!(
+            "{CAMERA}definitions:\n  ball:\n{}objects:\n{}{}{}{}",
+            sphere(0.0, 0.8),
+            sphere(-2.0, 0.5),
+            sphere(0.0, 0.8),
+            sphere(2.0, 0.5),
+            "  - { type: Instance, of: ball, transform: { translate: { x: 4.0, y: 0.0, z: 0.0 } } }\n"
+        );
+        let Ok(scene) = load_scene("ids", &yaml) else {
+            panic!("scene failed to load");
+        };
+
+        let ids = |x: f64| {
+            let ray = Ray::new(Vec3::new(x, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+            let rec = scene
+                .world
+                .hit(&ray, Interval::new(0.001, f64::INFINITY))
+                .unwrap();
+            (rec.object_id, rec.material_id)
+        };
+        assert_eq!(ids(-2.0), (1, 2));
+        assert_eq!(ids(0.0), (2, 1));
+        assert_eq!(ids(2.0), (3, 2));
+        assert_eq!(ids(4.0), (4, 1));
+    }
+
+    #[test]
+    fn test_filter_settings() {
+        let yaml = format!("{CAMERA}objects: []\nfilter: {{ type: Mitchell }}\n");
+        let Ok(scene) = load_scene("filter", &yaml) else {
+            panic!("scene failed to load");
+        };
+        assert_eq!(scene.filter, Filter::new(FilterType::Mitchell));
+
+        let yaml = format!("{CAMERA}objects: []\nfilter: {{ type: Gaussian, radius: 0.25 }}\n");
+        let err = load("filter-radius", &yaml).unwrap_err();
+        assert_eq!(err.context.as_deref(), Some("filter"));
+        assert!(invalid_message(err).contains("radius"));
+    }
+
+    #[test]
+    fn test_tonemap_settings() {
+        let yaml = format!(
+            "{CAMERA}objects: []\ntonemap: {{ operator: ReinhardExtended, white_point: 4.0 }}\n"
+        );
+        let Ok(scene) = load_scene("tonemap", &yaml) else {
+            panic!("scene failed to load");
+        };
+        assert_eq!(scene.tonemap.operator, ToneMapOperator::ReinhardExtended);
+        assert_eq!(scene.tonemap.white_point, Some(4.0));
+
+        for (name, white_point) in [("zero", "0.0"), ("nan", ".nan"), ("inf", ".inf")] {
+            let yaml = format!("{CAMERA}objects: []\ntonemap: {{ white_point: {white_point} }}\n");
+            let err = load(&format!("white-{name}"), &yaml).unwrap_err();
+            assert_eq!(err.context.as_deref(), Some("tonemap"));
+            assert!(
+                invalid_message(err).contains("white_point"),
+                "{white_point}"
+            );
+        }
+
+        let yaml = format!("{CAMERA}objects: []\ntonemap: {{ exposure: .nan }}\n");
+        let err = load("exposure-nan", &yaml).unwrap_err();
+        assert!(invalid_message(err).contains("exposure"));
+    }
+
+    #[test]
+    fn test_syntax_error_has_location() {
+        let yaml = format!("{CAMERA}objects:\n  - type: Sphere\n    radius: 1.0\n");
+        let err = load("syntax", &yaml).unwrap_err();
+        assert!(matches!(err.kind, SceneErrorKind::Syntax(_)));
+        assert_eq!(
+            err.location,
+            Some(Location {
+                line: 10,
+                column: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_negative_radius() {
+        let yaml = format!(
+            "{CAMERA}objects:
+  - type: Sphere
+    center: {{ x: 0.0, y: 0.0, z: 0.0 }}
+    radius: -1.0
+    material: {{ type: Lambertian, albedo: {{ x: 0.5, y: 0.5, z: 0.5 }} }}
+"
+        );
+        let err = load("radius", &yaml).unwrap_err();
+        assert_eq!(err.context.as_deref(), Some("objects[0]"));
+        assert!(invalid_message(err).contains("radius"));
+    }
+
+    #[test]
+    fn test_degenerate_triangle() {
+        let yaml = format!(
+            "{CAMERA}objects:
+  - type: Triangle
+    v0: {{ x: 0.0, y: 0.0, z: 0.0 }}
+    v1: {{ x: 1.0, y: 1.0, z: 1.0 }}
+    v2: {{ x: 2.0, y: 2.0, z: 2.0 }}
+    material: {{ type: Lambertian, albedo: {{ x: 0.5, y: 0.5, z: 0.5 }} }}
+"
+        );
+        let err = load("triangle", &yaml).unwrap_err();
+        assert!(invalid_message(err).contains("degenerate"));
+    }
+
+    #[test]
+    fn test_degenerate_quad_and_box() {
+        let yaml = format!(
+            "{CAMERA}objects:
+  - type: Quad
+    corner: {{ x: 0.0, y: 0.0, z: 0.0 }}
+    u: {{ x: 1.0, y: 0.0, z: 0.0 }}
+    v: {{ x: 2.0, y: 0.0, z: 0.0 }}
+    material: {{ type: Lambertian, albedo: {{ x: 0.5, y: 0.5, z: 0.5 }} }}
+"
+        );
+        let err = load("quad", &yaml).unwrap_err();
+        assert!(invalid_message(err).contains("degenerate"));
+
+        let yaml = format!(
+            "{CAMERA}objects:
+  - type: Box
+    min: {{ x: 0.0, y: 0.0, z: 0.0 }}
+    max: {{ x: 1.0, y: 0.0, z: 1.0 }}
+    material: {{ type: Lambertian, albedo: {{ x: 0.5, y: 0.5, z: 0.5 }} }}
+"
+        );
+        let err = load("box", &yaml).unwrap_err();
+        assert_eq!(err.context.as_deref(), Some("objects[0]"));
+        assert!(invalid_message(err).contains("box"));
+    }
+
+    #[test]
+    fn test_plane_and_disk_checks() {
+        let yaml = format!(
+            "{CAMERA}objects:
+  - type: Plane
+    point: {{ x: 0.0, y: 0.0, z: 0.0 }}
+    normal: {{ x: 0.0, y: 0.0, z: 0.0 }}
+    material: {{ type: Lambertian, albedo: {{ x: 0.5, y: 0.5, z: 0.5 }} }}
+"
+        );
+        let err = load("plane", &yaml).unwrap_err();
+        assert!(invalid_message(err).contains("normal"));
+
+        let yaml = format!(
+            "{CAMERA}objects:
+  - type: Plane
+    point: {{ x: 0.0, y: -1.0, z: 0.0 }}
+    normal: {{ x: 0.0, y: 1.0, z: 0.0 }}
+    material: {{ type: Lambertian, albedo: {{ x: 0.5, y: 0.5, z: 0.5 }} }}
+  - type: Disk
+    center: {{ x: 0.0, y: 0.0, z: 0.0 }}
+    normal: {{ x: 0.0, y: 0.0, z: 1.0 }}
+    radius: 0.5
+    material: {{ type: Lambertian, albedo: {{ x: 0.5, y: 0.5, z: 0.5 }} }}
+"
         );
+        let Ok(scene) = load_scene("plane-disk", &yaml) else {
+            panic!("scene failed to load");
+        };
+        assert!(!scene.world.bounding_box().is_bounded());
+        assert!(scene.world.bounded_box().is_bounded());
+    }
+
+    #[test]
+    fn test_torus_radii() {
+        let torus = |major: f64, minor: f64| {
+            format!(
+                "{CAMERA}objects:
+  - type: Torus
+    center: {{ x: 0.0, y: 0.0, z: 0.0 }}
+    major_radius: {major}
+    minor_radius: {minor}
+    material: {{ type: Lambertian, albedo: {{ x: 0.5, y: 0.5, z: 0.5 }} }}
+"
+            )
+        };
+        assert!(load_scene("torus", &torus(1.0, 0.25)).is_ok());
+
+        let err = load("torus", &torus(0.5, 1.0)).unwrap_err();
+        assert_eq!(err.context.as_deref(), Some("objects[0]"));
+        assert!(invalid_message(err).contains("minor_radius"));
+        let err = load("torus", &torus(1.0, 0.0)).unwrap_err();
+        assert!(invalid_message(err).contains("minor_radius"));
+    }
+
+    #[test]
+    fn test_volume_checks() {
+        let volume = |density: f64, material: &str| {
+            format!(
+                "{CAMERA}objects:
+  - type: Volume
+    density: {density}
+    material: {material}
+    boundary:
+      type: Sphere
+      center: {{ x: 0.0, y: 0.0, z: 0.0 }}
+      radius: 1.0
+      material: {{ type: Dielectric, index: 1.5 }}
+"
+            )
+        };
+        let isotropic = "{ type: Isotropic, albedo: { x: 0.9, y: 0.9, z: 0.9 } }";
+        let Ok(scene) = load_scene("volume", &volume(0.5, isotropic)) else {
+            panic!("scene failed to load");
+        };
+        assert!(scene.world.bounding_box().is_bounded());
+
+        let err = load("volume", &volume(0.0, isotropic)).unwrap_err();
+        assert!(invalid_message(err).contains("density"));
+
+        let lambertian = "{ type: Lambertian, albedo: { x: 0.9, y: 0.9, z: 0.9 } }";
+        let err = load("volume", &volume(0.5, lambertian)).unwrap_err();
+        assert!(invalid_message(err).contains("phase function"));
+
+        let forward = "{ type: HenyeyGreenstein, albedo: { x: 0.9, y: 0.9, z: 0.9 }, g: 1.0 }";
+        let err = load("volume", &volume(0.5, forward)).unwrap_err();
+        assert!(invalid_message(err).contains("between -1 and 1"));
+    }
+
+    #[test]
+    fn test_atmosphere_settings() {
+        let with_atmosphere =
+            |atmosphere: &str| format!("{CAMERA}atmosphere: {atmosphere}\nobjects: []\n");
+
+        let Ok(scene) = load_scene("fog", &format!("{CAMERA}objects: []\n")) else {
+            panic!("scene failed to load");
+        };
+        assert!(scene.atmosphere.is_none());
+        let height_fog = "{ type: HeightFog, density: 0.2, falloff: 0.5, g: 0.3 }";
+        let Ok(scene) = load_scene("fog", &with_atmosphere(height_fog)) else {
+            panic!("scene failed to load");
+        };
+        assert!(scene.atmosphere.is_some());
+
+        let err = load("fog", &with_atmosphere("{ density: -1.0 }")).unwrap_err();
+        assert_eq!(err.context.as_deref(), Some("atmosphere"));
+        assert!(invalid_message(err).contains("density"));
+
+        let err = load("fog", &with_atmosphere("{ type: HeightFog, density: 0.2 }")).unwrap_err();
+        assert!(invalid_message(err).contains("falloff"));
+
+        let err = load("fog", &with_atmosphere("{ density: 0.2, falloff: 1.0 }")).unwrap_err();
+        assert!(invalid_message(err).contains("HeightFog"));
+
+        let flat = "{ density: 0.2, bounds: { min: { x: 0.0, y: 0.0, z: 0.0 }, \
+                    max: { x: 1.0, y: 0.0, z: 1.0 } } }";
+        let err = load("fog", &with_atmosphere(flat)).unwrap_err();
+        assert!(invalid_message(err).contains("bounds"));
+    }
+
+    #[test]
+    fn test_homogeneous_fog_fills_the_scene_box() {
+        let escape = |atmosphere: &str| {
+            let yaml = format!(
+                "{CAMERA}atmosphere: {atmosphere}
+objects:
+  - type: Sphere
+    center: {{ x: 0.0, y: 0.0, z: -3.0 }}
+    radius: 1.0
+    material: {{ type: Lambertian, albedo: {{ x: 0.5, y: 0.5, z: 0.5 }} }}
+"
+            );
+            let Ok(Scene {
+                atmosphere: Some(atmosphere),
+                ..
+            }) = load_scene("fog_extent", &yaml)
+            else {
+                panic!("scene failed to load");
+            };
+            let ray = Ray::new(Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+            atmosphere.transmittance(&ray, f64::INFINITY)
+        };
+
+        // The box around the sphere and the camera spans z from -4 to 1.
+        let expected = (-0.2 * 5.0f64).exp();
+        let fitted = escape("{ density: 0.2 }");
+        assert!((fitted - expected).abs() < 1e-4, "{fitted} vs {expected}");
+        let bounded = "{ density: 0.2, bounds: { min: { x: -1.0, y: -1.0, z: -1.0 }, \
+                       max: { x: 1.0, y: 1.0, z: 1.0 } } }";
+        assert!((escape(bounded) - (-0.4f64).exp()).abs() < 1e-9);
+        let height_fog = "{ type: HeightFog, density: 0.2, falloff: 0.5 }";
+        assert_eq!(escape(height_fog), 0.0);
+    }
+
+    #[test]
+    fn test_camera_checks() {
+        let bad_fov = CAMERA.replace("fov: 40.0", "fov: 180.0");
+        let err = load("fov", &format!("{bad_fov}objects: []\n")).unwrap_err();
+        assert_eq!(err.context.as_deref(), Some("camera"));
+        assert!(invalid_message(err).contains("fov"));
+
+        let bad_vup = CAMERA.replace(
+            "vup: { x: 0.0, y: 1.0, z: 0.0 }",
+            "vup: { x: 0.0, y: 0.0, z: 0.0 }",
+        );
+        let err = load("vup", &format!("{bad_vup}objects: []\n")).unwrap_err();
+        assert!(invalid_message(err).contains("vup"));
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let Err(err) = Engine::new(1, 1).load_scene_from_file("does/not/exist.yaml", 10) else {
+            panic!("loading a missing file should fail");
+        };
+        assert!(matches!(err.kind, SceneErrorKind::Io(_)));
+        assert!(err.to_string().starts_with("does/not/exist.yaml: "));
+    }
+
+    /// A lit sphere under a solid sky, small enough to render in a test.
+    fn small_scene(name: &str) -> Scene {
+        let yaml = format!(
+            "{CAMERA}background: {{ type: Solid, color: {{ x: 0.5, y: 0.7, z: 1.0 }} }}
+objects:
+  - type: Sphere
+    center: {{ x: 0.0, y: 0.0, z: -1.0 }}
+    radius: 0.3
+    material: {{ type: Lambertian, albedo: {{ x: 0.5, y: 0.5, z: 0.5 }} }}
+"
+        );
+        let Ok(scene) = load_scene(name, &yaml) else {
+            panic!("scene failed to load");
+        };
+        scene
+    }
+
+    #[test]
+    fn test_progressive_passes_stop_at_max_samples() {
+        let scene = small_scene("passes");
+        let engine = Engine::new(1, 4)
+            .with_seed(3)
+            .with_progressive(Progressive {
+                pass_samples: 16,
+                max_samples: Some(20),
+                time_limit: None,
+            });
+
+        // A full pass of 16, then the remaining 4.
+        let mut passes = Vec::new();
+        let output = engine.render_with_progress(&scene, |output| {
+            passes.push(output.sample_counts[0]);
+        });
+        assert_eq!(passes, [16, 20]);
+        assert!(output.sample_counts.iter().all(|&count| count == 20));
+    }
+
+    #[test]
+    fn test_progressive_passes_stop_once_converged() {
+        // With nothing in the way every ray sees the flat sky, so every
+        // pixel converges after its minimum samples.
+        let mut scene = small_scene("converged");
+        scene.world = HittableList::default();
+        let engine = Engine::new(1, 4)
+            .with_seed(3)
+            .with_adaptive_sampling(AdaptiveSampling {
+                min_samples: 8,
+                threshold: 0.05,
+            })
+            .with_progressive(Progressive {
+                pass_samples: 8,
+                max_samples: None,
+                time_limit: None,
+            });
 
-        self.renderer.render(world, camera, &mut canvas, background);
-        canvas
+        let mut passes = 0;
+        let output = engine.render_with_progress(&scene, |_| passes += 1);
+        // The second pass takes no samples, which ends the render.
+        assert_eq!(passes, 2);
+        assert!(output.sample_counts.iter().all(|&count| count == 8));
     }
 }
@@ -0,0 +1,450 @@
+use crate::{
+    geometry::{
+        hittable::{HitRecord, Hittable, SharedHittable},
+        hittable_list::HittableList,
+    },
+    math::{
+        aabb::Aabb,
+        interval::Interval,
+        ray::Ray,
+        vec3::{Point3, Vec3},
+    },
+};
+
+/// Strategy used to partition primitives when building a [`Bvh`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SplitMethod {
+    /// Splits at the median centroid along the longest axis. Fast to build.
+    Median,
+    /// Picks the split minimizing the surface area heuristic. Faster to trace.
+    #[default]
+    Sah,
+}
+
+const MAX_LEAF_SIZE: usize = 4;
+const MAX_DEPTH: usize = 64;
+const SAH_BUCKETS: usize = 12;
+const SAH_TRAVERSAL_COST: f64 = 0.125;
+
+#[derive(Debug, Clone, Copy)]
+enum NodeKind {
+    Leaf {
+        first: usize,
+        count: usize,
+    },
+    /// The first child always directly follows its parent in the node array.
+    Interior {
+        second_child: usize,
+        axis: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BvhNode {
+    bbox: Aabb,
+    kind: NodeKind,
+}
+
+struct BuildPrimitive {
+    object: SharedHittable,
+    bbox: Aabb,
+    centroid: Point3,
+}
+
+/// A bounding volume hierarchy over the objects of a [`HittableList`].
+///
+/// Nodes are stored flattened in depth-first order so traversal only needs
+/// a small index stack. Objects without finite bounds, such as infinite
+/// planes, are kept out of the hierarchy and tested on every ray.
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    objects: Vec<SharedHittable>,
+    unbounded: Vec<SharedHittable>,
+    /// Emissive objects, so a BVH nested inside an instance can still be
+    /// sampled as a light.
+    lights: HittableList,
+}
+
+impl Bvh {
+    /// Builds a hierarchy using the default (SAH) split method.
+    pub fn new(list: &HittableList) -> Self {
+        Self::with_split_method(list, SplitMethod::default())
+    }
+
+    pub fn with_split_method(list: &HittableList, method: SplitMethod) -> Self {
+        let (bounded, unbounded): (Vec<_>, Vec<_>) = list
+            .objects()
+            .iter()
+            .cloned()
+            .partition(|object| object.bounding_box().is_bounded());
+        let mut primitives: Vec<BuildPrimitive> = bounded
+            .into_iter()
+            .map(|object| {
+                let bbox = object.bounding_box();
+                BuildPrimitive {
+                    object,
+                    bbox,
+                    centroid: bbox.centroid(),
+                }
+            })
+            .collect();
+
+        let mut nodes = Vec::with_capacity(2 * primitives.len());
+        if !primitives.is_empty() {
+            build_recursive(&mut nodes, &mut primitives, 0, 0, method);
+        }
+
+        Self {
+            nodes,
+            objects: primitives.into_iter().map(|p| p.object).collect(),
+            unbounded,
+            lights: list.lights(),
+        }
+    }
+}
+
+fn build_recursive(
+    nodes: &mut Vec<BvhNode>,
+    primitives: &mut [BuildPrimitive],
+    offset: usize,
+    depth: usize,
+    method: SplitMethod,
+) -> usize {
+    let bbox = primitives
+        .iter()
+        .fold(Aabb::EMPTY, |acc, p| Aabb::surrounding(acc, p.bbox));
+
+    let index = nodes.len();
+    nodes.push(BvhNode {
+        bbox,
+        kind: NodeKind::Leaf {
+            first: offset,
+            count: primitives.len(),
+        },
+    });
+
+    if primitives.len() <= MAX_LEAF_SIZE || depth >= MAX_DEPTH {
+        return index;
+    }
+
+    let centroid_bounds = primitives.iter().fold(Aabb::EMPTY, |acc, p| Aabb {
+        x: Interval::merge(acc.x, Interval::new(p.centroid.x, p.centroid.x)),
+        y: Interval::merge(acc.y, Interval::new(p.centroid.y, p.centroid.y)),
+        z: Interval::merge(acc.z, Interval::new(p.centroid.z, p.centroid.z)),
+    });
+    let axis = centroid_bounds.longest_axis();
+
+    // All centroids coincide: no split can separate them.
+    if centroid_bounds.axis_interval(axis).size() <= 0.0 {
+        return index;
+    }
+
+    let mid = match method {
+        SplitMethod::Median => split_median(primitives, axis),
+        SplitMethod::Sah => match split_sah(primitives, axis, &bbox, &centroid_bounds) {
+            Some(mid) => mid,
+            None => return index,
+        },
+    };
+
+    let (left, right) = primitives.split_at_mut(mid);
+    build_recursive(nodes, left, offset, depth + 1, method);
+    let second_child = build_recursive(nodes, right, offset + mid, depth + 1, method);
+
+    nodes[index].kind = NodeKind::Interior { second_child, axis };
+    index
+}
+
+fn split_median(primitives: &mut [BuildPrimitive], axis: usize) -> usize {
+    primitives
+        .sort_by(|a, b| axis_value(a.centroid, axis).total_cmp(&axis_value(b.centroid, axis)));
+    primitives.len() / 2
+}
+
+/// Bins centroids into buckets and evaluates the SAH cost at each bucket
+/// boundary. Returns `None` when keeping a leaf is cheaper than any split.
+fn split_sah(
+    primitives: &mut [BuildPrimitive],
+    axis: usize,
+    bbox: &Aabb,
+    centroid_bounds: &Aabb,
+) -> Option<usize> {
+    let extent = centroid_bounds.axis_interval(axis);
+    let bucket_of = |centroid: Point3| {
+        let offset = (axis_value(centroid, axis) - extent.min) / extent.size();
+        ((offset * SAH_BUCKETS as f64) as usize).min(SAH_BUCKETS - 1)
+    };
+
+    let mut counts = [0usize; SAH_BUCKETS];
+    let mut bounds = [Aabb::EMPTY; SAH_BUCKETS];
+    for p in primitives.iter() {
+        let b = bucket_of(p.centroid);
+        counts[b] += 1;
+        bounds[b] = Aabb::surrounding(bounds[b], p.bbox);
+    }
+
+    let parent_area = bbox.surface_area();
+    let mut best_cost = f64::INFINITY;
+    let mut best_split = 0;
+
+    for split in 0..SAH_BUCKETS - 1 {
+        let (mut left_box, mut right_box) = (Aabb::EMPTY, Aabb::EMPTY);
+        let (mut left_count, mut right_count) = (0, 0);
+
+        for b in 0..=split {
+            left_box = Aabb::surrounding(left_box, bounds[b]);
+            left_count += counts[b];
+        }
+        for b in split + 1..SAH_BUCKETS {
+            right_box = Aabb::surrounding(right_box, bounds[b]);
+            right_count += counts[b];
+        }
+
+        if left_count == 0 || right_count == 0 {
+            continue;
+        }
+
+        let cost = SAH_TRAVERSAL_COST
+            + (left_count as f64 * left_box.surface_area()
+                + right_count as f64 * right_box.surface_area())
+                / parent_area;
+
+        if cost < best_cost {
+            best_cost = cost;
+            best_split = split;
+        }
+    }
+
+    let leaf_cost = primitives.len() as f64;
+    if best_cost >= leaf_cost {
+        return None;
+    }
+
+    let mut mid = 0;
+    for i in 0..primitives.len() {
+        if bucket_of(primitives[i].centroid) <= best_split {
+            primitives.swap(i, mid);
+            mid += 1;
+        }
+    }
+
+    if mid == 0 || mid == primitives.len() {
+        return Some(split_median(primitives, axis));
+    }
+    Some(mid)
+}
+
+fn axis_value(p: Point3, axis: usize) -> f64 {
+    match axis {
+        1 => p.y,
+        2 => p.z,
+        _ => p.x,
+    }
+}
+
+impl Hittable for Bvh {
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
+        let mut current_interval = bounds;
+        let mut closest_hit: Option<HitRecord> = None;
+
+        for object in &self.unbounded {
+            if let Some(rec) = object.hit(ray, current_interval) {
+                current_interval.max = rec.t;
+                closest_hit = Some(rec);
+            }
+        }
+
+        if self.nodes.is_empty() {
+            return closest_hit;
+        }
+
+        let dir_is_neg = [
+            ray.direction.x < 0.0,
+            ray.direction.y < 0.0,
+            ray.direction.z < 0.0,
+        ];
+
+        let mut stack = [0usize; MAX_DEPTH + 2];
+        let mut stack_len = 1;
+
+        while stack_len > 0 {
+            stack_len -= 1;
+            let index = stack[stack_len];
+            let node = &self.nodes[index];
+
+            if !node.bbox.hit(ray, current_interval) {
+                continue;
+            }
+
+            match node.kind {
+                NodeKind::Leaf { first, count } => {
+                    for object in &self.objects[first..first + count] {
+                        if let Some(rec) = object.hit(ray, current_interval) {
+                            current_interval.max = rec.t;
+                            closest_hit = Some(rec);
+                        }
+                    }
+                }
+                NodeKind::Interior { second_child, axis } => {
+                    // Push the far child first so the near one is visited first.
+                    if dir_is_neg[axis] {
+                        stack[stack_len] = index + 1;
+                        stack[stack_len + 1] = second_child;
+                    } else {
+                        stack[stack_len] = second_child;
+                        stack[stack_len + 1] = index + 1;
+                    }
+                    stack_len += 2;
+                }
+            }
+        }
+
+        closest_hit
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        if !self.unbounded.is_empty() {
+            return Aabb::UNIVERSE;
+        }
+        self.nodes.first().map_or(Aabb::EMPTY, |node| node.bbox)
+    }
+
+    fn is_emissive(&self) -> bool {
+        !self.lights.is_empty()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        self.lights.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: Point3) -> Vec3 {
+        self.lights.random(origin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geometry::{plane::Plane, sphere::Sphere, triangle::Triangle},
+        imaging::material::{Lambertian, MaterialKind},
+        math::{
+            utils,
+            vec3::{Color, Vec3},
+        },
+    };
+
+    fn random_scene() -> HittableList {
+        // Fixed seed, so the scene and the probe rays after it are the same
+        // on every run.
+        utils::reseed(17);
+        let material = MaterialKind::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let mut world = HittableList::default();
+
+        for _ in 0..200 {
+            let center = Vec3::random_range(-10.0, 10.0);
+            world.add(Sphere::new(
+                center,
+                utils::random_range(0.05, 1.0),
+                material.clone(),
+            ));
+        }
+        for _ in 0..300 {
+            let v0 = Vec3::random_range(-10.0, 10.0);
+            world.add(Triangle::new(
+                v0,
+                v0 + Vec3::random_range(-1.0, 1.0),
+                v0 + Vec3::random_range(-1.0, 1.0),
+                material.clone(),
+            ));
+        }
+        // Axis-aligned triangle, whose box is flat along z.
+        world.add(Triangle::new(
+            Vec3::new(-1.0, -1.0, 0.0),
+            Vec3::new(1.0, -1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            material,
+        ));
+
+        world
+    }
+
+    fn assert_same_hits(world: &HittableList, bvh: &Bvh) {
+        for _ in 0..5000 {
+            let ray = Ray::new(Vec3::random_range(-15.0, 15.0), Vec3::random_unit_vector());
+            let bounds = Interval::new(0.001, f64::INFINITY);
+
+            match (world.hit(&ray, bounds), bvh.hit(&ray, bounds)) {
+                (None, None) => {}
+                (Some(a), Some(b)) => {
+                    assert_eq!(a.t, b.t);
+                    assert_eq!(a.p, b.p);
+                    assert_eq!(a.normal, b.normal);
+                }
+                (a, b) => panic!(
+                    "list hit: {:?}, bvh hit: {:?}",
+                    a.map(|r| r.t),
+                    b.map(|r| r.t)
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_sah_matches_linear_list() {
+        let world = random_scene();
+        let bvh = Bvh::with_split_method(&world, SplitMethod::Sah);
+        assert_same_hits(&world, &bvh);
+    }
+
+    #[test]
+    fn test_median_matches_linear_list() {
+        let world = random_scene();
+        let bvh = Bvh::with_split_method(&world, SplitMethod::Median);
+        assert_same_hits(&world, &bvh);
+    }
+
+    #[test]
+    fn test_axis_aligned_triangle_is_hit() {
+        let world = random_scene();
+        let bvh = Bvh::new(&world);
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 50.0), Vec3::new(0.0, 0.0, -1.0));
+        let bounds = Interval::new(0.001, f64::INFINITY);
+        assert_eq!(
+            world.hit(&ray, bounds).map(|r| r.t),
+            bvh.hit(&ray, bounds).map(|r| r.t)
+        );
+    }
+
+    #[test]
+    fn test_empty_list() {
+        let bvh = Bvh::new(&HittableList::default());
+        let ray = Ray::new(Vec3::default(), Vec3::new(0.0, 0.0, -1.0));
+        assert!(bvh.hit(&ray, Interval::new(0.001, f64::INFINITY)).is_none());
+        assert!(bvh.bounding_box().is_empty());
+    }
+
+    #[test]
+    fn test_bounding_box_matches_list() {
+        let world = random_scene();
+        let bvh = Bvh::new(&world);
+        let (a, b) = (world.bounding_box(), bvh.bounding_box());
+        assert_eq!((a.x.min, a.x.max), (b.x.min, b.x.max));
+        assert_eq!((a.y.min, a.y.max), (b.y.min, b.y.max));
+        assert_eq!((a.z.min, a.z.max), (b.z.min, b.z.max));
+    }
+
+    #[test]
+    fn test_unbounded_objects_are_kept_out_of_the_tree() {
+        let mut world = random_scene();
+        world.add(Plane::new(
+            Vec3::new(0.0, -5.0, 0.0),
+            Vec3::new(0.1, 1.0, 0.0),
+            MaterialKind::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        ));
+        let bvh = Bvh::new(&world);
+        assert!(!bvh.bounding_box().is_bounded());
+        assert!(bvh.nodes[0].bbox.is_bounded());
+        assert_same_hits(&world, &bvh);
+    }
+}
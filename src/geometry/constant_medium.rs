@@ -0,0 +1,131 @@
+use crate::{
+    geometry::hittable::{HitRecord, Hittable, SharedHittable},
+    imaging::material::MaterialKind,
+    math::{aabb::Aabb, interval::Interval, ray::Ray, utils},
+};
+
+/// A uniformly dense volume, such as fog or smoke, filling a closed
+/// `boundary`.
+///
+/// A ray inside the volume scatters after an exponentially distributed
+/// distance, so thin volumes let most light through. The hit is a point in
+/// the volume whose `phase` material, usually
+/// [`Isotropic`](crate::imaging::material::Isotropic), picks the new
+/// direction.
+pub struct ConstantMedium {
+    boundary: SharedHittable,
+    neg_inv_density: f64,
+    phase: MaterialKind,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: SharedHittable, density: f64, phase: MaterialKind) -> Self {
+        Self {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase,
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
+        // Where the ray enters and leaves the boundary, over the whole line,
+        // so that rays starting inside the volume still find their way out.
+        let entry = self.boundary.hit(ray, Interval::UNIVERSE)?;
+        let exit = self
+            .boundary
+            .hit(ray, Interval::new(entry.t + 0.0001, f64::INFINITY))?;
+
+        let t_enter = entry.t.max(bounds.min).max(0.0);
+        let t_exit = exit.t.min(bounds.max);
+        if t_enter >= t_exit {
+            return None;
+        }
+
+        let speed = ray.direction.length();
+        let inside = (t_exit - t_enter) * speed;
+        let distance = self.neg_inv_density * (1.0 - utils::random()).ln();
+        if distance > inside {
+            return None;
+        }
+
+        // There is no surface, so the normal only has to face the ray.
+        let t = t_enter + distance / speed;
+        Some(HitRecord::new(
+            ray.at(t),
+            -ray.direction / speed,
+            t,
+            (0.0, 0.0),
+            ray,
+            &self.phase,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geometry::sphere::Sphere,
+        imaging::material::{Isotropic, Lambertian},
+        math::vec3::{Color, Point3, Vec3},
+    };
+    use std::sync::Arc;
+
+    fn fog(density: f64) -> ConstantMedium {
+        let boundary = Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            1.0,
+            MaterialKind::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        ConstantMedium::new(
+            Arc::new(boundary),
+            density,
+            MaterialKind::Isotropic(Isotropic::new(Color::new(1.0, 1.0, 1.0))),
+        )
+    }
+
+    #[test]
+    fn test_transmittance_follows_beer_lambert() {
+        let medium = fog(0.5);
+        let ray = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 2.0));
+        utils::reseed(8);
+        let n = 20_000;
+        let mut passed = 0;
+        for _ in 0..n {
+            match medium.hit(&ray, Interval::new(0.001, f64::INFINITY)) {
+                Some(rec) => {
+                    assert!(rec.p.length() <= 1.0 + 1e-9);
+                    assert!(rec.front_face);
+                }
+                None => passed += 1,
+            }
+        }
+
+        // Two units of fog at density 0.5 let e⁻¹ of the rays through.
+        let transmitted = passed as f64 / n as f64;
+        assert!(
+            (transmitted - (-1.0f64).exp()).abs() < 0.01,
+            "{transmitted}"
+        );
+    }
+
+    #[test]
+    fn test_rays_starting_inside_are_scattered() {
+        let medium = fog(1e6);
+        let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        let rec = medium
+            .hit(&ray, Interval::new(0.001, f64::INFINITY))
+            .expect("dense fog should scatter");
+        assert!(rec.t < 0.01);
+
+        // Nothing when the allowed interval ends before the volume.
+        let outside = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(medium.hit(&outside, Interval::new(0.001, 3.0)).is_none());
+    }
+}
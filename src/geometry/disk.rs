@@ -0,0 +1,154 @@
+use std::f64::consts::PI;
+
+use crate::{
+    geometry::hittable::{HitRecord, Hittable},
+    imaging::material::MaterialKind,
+    math::{
+        aabb::Aabb,
+        interval::Interval,
+        onb::Onb,
+        ray::Ray,
+        vec3::{Point3, Vec3},
+    },
+};
+
+/// A flat, round disk facing `normal`. The texture coordinates are polar:
+/// u is the angle around the center, v the distance from it over the
+/// radius.
+pub struct Disk {
+    center: Point3,
+    radius: f64,
+    axes: Onb,
+    material: MaterialKind,
+    bbox: Aabb,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f64, material: MaterialKind) -> Self {
+        let axes = Onb::new(normal);
+        let n = axes.w;
+        // Half-extent of the disk along each axis.
+        let extent = Vec3::new(
+            radius * (1.0 - n.x * n.x).max(0.0).sqrt(),
+            radius * (1.0 - n.y * n.y).max(0.0).sqrt(),
+            radius * (1.0 - n.z * n.z).max(0.0).sqrt(),
+        );
+
+        Self {
+            center,
+            radius,
+            axes,
+            material,
+            bbox: Aabb::from_points(center - extent, center + extent),
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
+        let normal = self.axes.w;
+        let denom = normal.dot(ray.direction);
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = normal.dot(self.center - ray.origin) / denom;
+        if !bounds.surrounds(t) {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let offset = p - self.center;
+        let distance_squared = offset.length_squared();
+        if distance_squared > self.radius * self.radius {
+            return None;
+        }
+
+        let angle = offset.dot(self.axes.v).atan2(offset.dot(self.axes.u));
+        let uv = (
+            (angle + PI) / (2.0 * PI),
+            distance_squared.sqrt() / self.radius,
+        );
+
+        Some(HitRecord::new(p, normal, t, uv, ray, &self.material))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        let Some(rec) = self.hit(
+            &Ray::new(origin, direction),
+            Interval::new(0.001, f64::INFINITY),
+        ) else {
+            return 0.0;
+        };
+
+        let distance_squared = rec.t * rec.t * direction.length_squared();
+        let cosine = (direction.dot(self.axes.w) / direction.length()).abs();
+        if cosine < 1e-8 {
+            return 0.0;
+        }
+
+        distance_squared / (cosine * PI * self.radius * self.radius)
+    }
+
+    /// Samples a point uniformly over the disk's area.
+    fn random(&self, origin: Point3) -> Vec3 {
+        let p = Vec3::random_in_unit_disk() * self.radius;
+        self.center + self.axes.u * p.x + self.axes.v * p.y - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        imaging::material::Lambertian,
+        math::{utils, vec3::Color},
+    };
+
+    fn tilted_disk() -> Disk {
+        let material = MaterialKind::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        Disk::new(
+            Point3::new(0.0, 2.0, 0.0),
+            Vec3::new(0.3, -1.0, 0.2),
+            0.8,
+            material,
+        )
+    }
+
+    #[test]
+    fn test_hits_stay_within_radius_and_box() {
+        let disk = tilted_disk();
+        utils::reseed(9);
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let ray = Ray::new(Point3::default(), Vec3::random_unit_vector());
+            if let Some(rec) = disk.hit(&ray, Interval::new(0.001, f64::INFINITY)) {
+                hits += 1;
+                assert!((rec.p - disk.center).length() <= 0.8 + 1e-9);
+                assert!(disk.bounding_box().contains(rec.p));
+                assert!((0.0..=1.0).contains(&rec.u) && (0.0..=1.0).contains(&rec.v));
+            }
+        }
+        assert!(hits > 0);
+    }
+
+    #[test]
+    fn test_pdf_integrates_to_one() {
+        let disk = tilted_disk();
+        utils::reseed(5);
+        let n = 200_000;
+        let sum: f64 = (0..n)
+            .map(|_| disk.pdf_value(Point3::default(), Vec3::random_unit_vector()))
+            .sum();
+        let integral = sum / n as f64 * 4.0 * PI;
+        assert!((integral - 1.0).abs() < 0.03, "{integral}");
+    }
+}
@@ -1,19 +1,39 @@
+use std::sync::Arc;
+
 use crate::{
     imaging::material::MaterialKind,
-    math::{interval::Interval, ray::Ray, vec3::Vec3},
+    math::{
+        aabb::Aabb,
+        interval::Interval,
+        ray::Ray,
+        vec3::{Point3, Vec3},
+    },
 };
 
-pub struct HitRecord {
+pub struct HitRecord<'a> {
     pub t: f64,
     pub p: Vec3,
     pub normal: Vec3,
-    pub material: MaterialKind,
+    /// Surface texture coordinates of the hit.
+    pub u: f64,
+    pub v: f64,
+    pub material: &'a MaterialKind,
     pub front_face: bool,
+    /// IDs set by enclosing `Tagged` objects; 0 when untagged.
+    pub object_id: u32,
+    pub material_id: u32,
 }
 
-impl HitRecord {
+impl<'a> HitRecord<'a> {
     /// Creates a new HitRecord, ensuring the normal points against the ray.
-    pub fn new(p: Vec3, normal: Vec3, t: f64, ray: &Ray, material: MaterialKind) -> Self {
+    pub fn new(
+        p: Vec3,
+        normal: Vec3,
+        t: f64,
+        (u, v): (f64, f64),
+        ray: &Ray,
+        material: &'a MaterialKind,
+    ) -> Self {
         let front_face = ray.direction.dot(normal) < 0.0;
         let normal = if front_face { normal } else { -normal };
 
@@ -21,12 +41,70 @@ impl HitRecord {
             t,
             p,
             normal,
+            u,
+            v,
             material,
             front_face,
+            object_id: 0,
+            material_id: 0,
         }
     }
+
+    /// Replaces the geometric normal with an interpolated shading normal,
+    /// keeping it on the side the ray came from.
+    pub fn with_shading_normal(mut self, shading_normal: Vec3) -> Self {
+        let n = shading_normal.unit_vector();
+        self.normal = if n.dot(self.normal) < 0.0 { -n } else { n };
+        self
+    }
 }
 
 pub trait Hittable {
-    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord>;
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>>;
+
+    /// Returns a box enclosing the whole object.
+    fn bounding_box(&self) -> Aabb;
+
+    /// Returns true if the object emits light and can be sampled directly.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    /// Solid-angle density, as seen from `origin`, of [`Hittable::random`]
+    /// picking `direction`. Zero if the direction misses the object.
+    fn pdf_value(&self, _origin: Point3, _direction: Vec3) -> f64 {
+        0.0
+    }
+
+    /// Returns a (non-normalized) direction from `origin` towards a random
+    /// point on the object.
+    fn random(&self, _origin: Point3) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
+}
+
+/// A hittable that can be shared between containers and across threads.
+pub type SharedHittable = Arc<dyn Hittable + Send + Sync>;
+
+/// Lets shared geometry be wrapped (e.g. by `Transformed`) without copying.
+impl<T: Hittable + ?Sized> Hittable for Arc<T> {
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
+        (**self).hit(ray, bounds)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        (**self).bounding_box()
+    }
+
+    fn is_emissive(&self) -> bool {
+        (**self).is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        (**self).pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: Point3) -> Vec3 {
+        (**self).random(origin)
+    }
 }
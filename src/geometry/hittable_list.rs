@@ -1,26 +1,71 @@
 use crate::{
-    geometry::hittable::{HitRecord, Hittable},
-    math::{interval::Interval, ray::Ray},
+    geometry::hittable::{HitRecord, Hittable, SharedHittable},
+    math::{
+        aabb::Aabb,
+        interval::Interval,
+        ray::Ray,
+        utils,
+        vec3::{Point3, Vec3},
+    },
 };
-use std::boxed::Box;
+use std::sync::Arc;
 
 #[derive(Default)]
 pub struct HittableList {
-    hittables: Vec<Box<dyn Hittable + Send + Sync>>,
+    hittables: Vec<SharedHittable>,
+    bbox: Aabb,
 }
 
 impl HittableList {
     pub fn add<T: Hittable + 'static + Send + Sync>(&mut self, hittable: T) {
-        self.hittables.push(Box::new(hittable));
+        self.add_shared(Arc::new(hittable));
+    }
+
+    /// Adds an object that may also be referenced from elsewhere.
+    pub fn add_shared(&mut self, hittable: SharedHittable) {
+        self.bbox = Aabb::surrounding(self.bbox, hittable.bounding_box());
+        self.hittables.push(hittable);
+    }
+
+    pub fn objects(&self) -> &[SharedHittable] {
+        &self.hittables
+    }
+
+    /// Collects the emissive objects, to be used for direct light sampling.
+    pub fn lights(&self) -> HittableList {
+        let mut lights = HittableList::default();
+        for hittable in self.hittables.iter().filter(|h| h.is_emissive()) {
+            lights.add_shared(hittable.clone());
+        }
+        lights
+    }
+
+    /// The box around every object with finite bounds, leaving out
+    /// unbounded ones such as infinite planes.
+    pub fn bounded_box(&self) -> Aabb {
+        self.hittables
+            .iter()
+            .map(|h| h.bounding_box())
+            .filter(Aabb::is_bounded)
+            .fold(Aabb::EMPTY, Aabb::surrounding)
+    }
+
+    pub fn len(&self) -> usize {
+        self.hittables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hittables.is_empty()
     }
 
     pub fn _clear(&mut self) {
         self.hittables.clear();
+        self.bbox = Aabb::EMPTY;
     }
 }
 
 impl Hittable for HittableList {
-    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
         let mut current_interval: Interval = bounds;
         let mut closest_hit: Option<HitRecord> = None;
 
@@ -33,4 +78,35 @@ impl Hittable for HittableList {
 
         closest_hit
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.hittables.iter().any(|h| h.is_emissive())
+    }
+
+    /// Objects are picked uniformly, so the density is the average of theirs.
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        if self.hittables.is_empty() {
+            return 0.0;
+        }
+
+        let sum: f64 = self
+            .hittables
+            .iter()
+            .map(|h| h.pdf_value(origin, direction))
+            .sum();
+        sum / self.hittables.len() as f64
+    }
+
+    fn random(&self, origin: Point3) -> Vec3 {
+        if self.hittables.is_empty() {
+            return Vec3::new(1.0, 0.0, 0.0);
+        }
+
+        let index = (utils::random() * self.hittables.len() as f64) as usize;
+        self.hittables[index.min(self.hittables.len() - 1)].random(origin)
+    }
 }
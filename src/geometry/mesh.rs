@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use crate::{
+    geometry::{
+        hittable::{HitRecord, Hittable},
+        triangle,
+    },
+    imaging::material::MaterialKind,
+    math::{
+        aabb::Aabb,
+        interval::Interval,
+        ray::Ray,
+        vec3::{Point3, Vec3},
+    },
+};
+
+/// Vertex indices of one triangular face. Normal and UV indices are only
+/// present when the source provided them for every corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshFace {
+    pub positions: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+    pub uvs: Option<[usize; 3]>,
+}
+
+/// Indexed triangle mesh. Vertex data is stored once and shared by all the
+/// [`MeshTriangle`]s that reference it.
+#[derive(Debug)]
+pub struct TriangleMesh {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<(f64, f64)>,
+    pub faces: Vec<MeshFace>,
+    pub material: MaterialKind,
+}
+
+impl TriangleMesh {
+    /// Creates one hittable per face, all sharing this mesh's buffers.
+    pub fn triangles(mesh: &Arc<TriangleMesh>) -> Vec<MeshTriangle> {
+        (0..mesh.faces.len())
+            .map(|face| MeshTriangle::new(mesh.clone(), face))
+            .collect()
+    }
+
+    fn vertices(&self, face: &MeshFace) -> [Point3; 3] {
+        face.positions.map(|i| self.positions[i])
+    }
+}
+
+/// A single face of a [`TriangleMesh`].
+pub struct MeshTriangle {
+    mesh: Arc<TriangleMesh>,
+    face: usize,
+    normal: Vec3,
+    area: f64,
+}
+
+impl MeshTriangle {
+    pub fn new(mesh: Arc<TriangleMesh>, face: usize) -> Self {
+        let [v0, v1, v2] = mesh.vertices(&mesh.faces[face]);
+        let cross = (v1 - v0).cross(v2 - v0);
+
+        Self {
+            normal: cross.unit_vector(),
+            area: 0.5 * cross.length(),
+            mesh,
+            face,
+        }
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
+        let face = &self.mesh.faces[self.face];
+        let [v0, v1, v2] = self.mesh.vertices(face);
+        let (t, b1, b2) = triangle::intersect(v0, v1, v2, ray, bounds)?;
+
+        // Without texture coordinates, fall back to the barycentrics.
+        let uv = match face.uvs {
+            Some([i0, i1, i2]) => {
+                let (uv0, uv1, uv2) = (self.mesh.uvs[i0], self.mesh.uvs[i1], self.mesh.uvs[i2]);
+                let b0 = 1.0 - b1 - b2;
+                (
+                    b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0,
+                    b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1,
+                )
+            }
+            None => (b1, b2),
+        };
+
+        let rec = HitRecord::new(ray.at(t), self.normal, t, uv, ray, &self.mesh.material);
+        Some(match face.normals {
+            Some(indices) => {
+                let normals = indices.map(|i| self.mesh.normals[i]);
+                rec.with_shading_normal(triangle::interpolate_normal(normals, b1, b2))
+            }
+            None => rec,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let [v0, v1, v2] = self.mesh.vertices(&self.mesh.faces[self.face]);
+        Aabb::surrounding(Aabb::from_points(v0, v1), Aabb::from_points(v0, v2))
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.mesh.material.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        triangle::area_pdf(
+            self.mesh.vertices(&self.mesh.faces[self.face]),
+            self.normal,
+            self.area,
+            origin,
+            direction,
+        )
+    }
+
+    fn random(&self, origin: Point3) -> Vec3 {
+        let [v0, v1, v2] = self.mesh.vertices(&self.mesh.faces[self.face]);
+        triangle::sample_area(v0, v1, v2) - origin
+    }
+}
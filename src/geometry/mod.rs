@@ -1,8 +1,29 @@
+pub mod bvh;
+pub mod constant_medium;
+pub mod disk;
 pub mod hittable;
 pub mod hittable_list;
+pub mod mesh;
+mod pdf;
+pub mod plane;
+pub mod quad;
+pub mod quadric;
 pub mod sphere;
+pub mod tagged;
+pub mod torus;
+pub mod transformed;
 pub mod triangle;
 
-pub use hittable::{HitRecord, Hittable};
+pub use bvh::{Bvh, SplitMethod};
+pub use constant_medium::ConstantMedium;
+pub use disk::Disk;
+pub use hittable::{HitRecord, Hittable, SharedHittable};
 pub use hittable_list::HittableList;
+pub use mesh::{MeshTriangle, TriangleMesh};
+pub use plane::Plane;
+pub use quad::{Quad, box_quads};
+pub use quadric::{Capsule, Cone, Cylinder};
 pub use sphere::Sphere;
+pub use tagged::{Tag, Tagged};
+pub use torus::Torus;
+pub use transformed::Transformed;
@@ -0,0 +1,31 @@
+use crate::{
+    geometry::hittable::Hittable,
+    math::{
+        interval::Interval,
+        ray::Ray,
+        vec3::{Point3, Vec3},
+    },
+};
+
+/// Solid-angle density of sampling `shape` uniformly by area, summed over
+/// every point of the shape along `direction`, since a sample may land
+/// behind the near side.
+pub(crate) fn area_pdf(shape: &dyn Hittable, area: f64, origin: Point3, direction: Vec3) -> f64 {
+    let ray = Ray::new(origin, direction);
+    let mut bounds = Interval::new(0.001, f64::INFINITY);
+    let mut pdf = 0.0;
+
+    // A ray crosses a quadric and its caps, or a torus, at most four times.
+    for _ in 0..4 {
+        let Some(rec) = shape.hit(&ray, bounds) else {
+            break;
+        };
+        let distance_squared = rec.t * rec.t * direction.length_squared();
+        let cosine = (direction.dot(rec.normal) / direction.length()).abs();
+        if cosine > 1e-8 {
+            pdf += distance_squared / (cosine * area);
+        }
+        bounds.min = rec.t + 1e-9 * rec.t.max(1.0);
+    }
+    pdf
+}
@@ -0,0 +1,109 @@
+use crate::{
+    geometry::hittable::{HitRecord, Hittable},
+    imaging::material::MaterialKind,
+    math::{
+        aabb::Aabb,
+        interval::Interval,
+        onb::Onb,
+        ray::Ray,
+        vec3::{Point3, Vec3},
+    },
+};
+
+/// An infinite plane through `point`, facing `normal`.
+///
+/// Texture coordinates are the hit's position along two in-plane axes,
+/// repeating every `texture_scale` units so image textures tile. Planes
+/// have no finite bounds, so they are never sampled as lights.
+pub struct Plane {
+    point: Point3,
+    axes: Onb,
+    /// Plane offset: `normal · p` for every point p on the plane.
+    d: f64,
+    texture_scale: f64,
+    material: MaterialKind,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3, material: MaterialKind) -> Self {
+        let axes = Onb::new(normal);
+        Self {
+            point,
+            axes,
+            d: axes.w.dot(point),
+            texture_scale: 1.0,
+            material,
+        }
+    }
+
+    /// Sets the size, in world units, of one tile of texture coordinates.
+    pub fn with_texture_scale(mut self, scale: f64) -> Self {
+        self.texture_scale = scale;
+        self
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
+        let normal = self.axes.w;
+        let denom = normal.dot(ray.direction);
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - normal.dot(ray.origin)) / denom;
+        if !bounds.surrounds(t) {
+            return None;
+        }
+
+        // Snap onto the plane, so that 3D textures such as checkers do not
+        // flicker between cells on an axis-aligned plane.
+        let hit = ray.at(t);
+        let p = hit - normal * (normal.dot(hit) - self.d);
+
+        let offset = (p - self.point) / self.texture_scale;
+        let uv = (
+            offset.dot(self.axes.u).rem_euclid(1.0),
+            offset.dot(self.axes.v).rem_euclid(1.0),
+        );
+
+        Some(HitRecord::new(p, normal, t, uv, ray, &self.material))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::UNIVERSE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{imaging::material::Lambertian, math::vec3::Color};
+
+    #[test]
+    fn test_hit_lies_exactly_on_axis_aligned_plane() {
+        let material = MaterialKind::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let plane = Plane::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            material,
+        )
+        .with_texture_scale(2.0);
+        let ray = Ray::new(Point3::new(0.3, 1.7, -5.1), Vec3::new(0.31, -0.9, 0.27));
+        let rec = plane
+            .hit(&ray, Interval::new(0.001, f64::INFINITY))
+            .expect("ray should hit the plane");
+
+        assert_eq!(rec.p.y, 0.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+        assert!((0.0..1.0).contains(&rec.u) && (0.0..1.0).contains(&rec.v));
+
+        let away = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert!(
+            plane
+                .hit(&away, Interval::new(0.001, f64::INFINITY))
+                .is_none()
+        );
+        assert!(!plane.bounding_box().is_bounded());
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{
+    geometry::hittable::{HitRecord, Hittable},
+    imaging::material::MaterialKind,
+    math::{
+        aabb::Aabb,
+        interval::Interval,
+        ray::Ray,
+        utils,
+        vec3::{Point3, Vec3},
+    },
+};
+
+/// A parallelogram spanned by two edges from a corner. The texture
+/// coordinates run from 0 to 1 along each edge.
+pub struct Quad {
+    corner: Point3,
+    u: Vec3,
+    v: Vec3,
+    /// `u × v` over its squared length, which turns the cross products of
+    /// an in-plane offset with the edges into edge coordinates.
+    w: Vec3,
+    normal: Vec3,
+    /// Plane offset: `normal · p` for every point p on the quad.
+    d: f64,
+    area: f64,
+    material: MaterialKind,
+    bbox: Aabb,
+}
+
+impl Quad {
+    pub fn new(corner: Point3, u: Vec3, v: Vec3, material: MaterialKind) -> Self {
+        let n = u.cross(v);
+        let normal = n.unit_vector();
+        let bbox = Aabb::surrounding(
+            Aabb::from_points(corner, corner + u + v),
+            Aabb::from_points(corner + u, corner + v),
+        );
+
+        Self {
+            corner,
+            u,
+            v,
+            w: n / n.dot(n),
+            normal,
+            d: normal.dot(corner),
+            area: n.length(),
+            material,
+            bbox,
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
+        let denom = self.normal.dot(ray.direction);
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - self.normal.dot(ray.origin)) / denom;
+        if !bounds.surrounds(t) {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let planar = p - self.corner;
+        let alpha = self.w.dot(planar.cross(self.v));
+        let beta = self.w.dot(self.u.cross(planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        Some(HitRecord::new(
+            p,
+            self.normal,
+            t,
+            (alpha, beta),
+            ray,
+            &self.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        let Some(rec) = self.hit(
+            &Ray::new(origin, direction),
+            Interval::new(0.001, f64::INFINITY),
+        ) else {
+            return 0.0;
+        };
+
+        let distance_squared = rec.t * rec.t * direction.length_squared();
+        let cosine = (direction.dot(self.normal) / direction.length()).abs();
+        if cosine < 1e-8 {
+            return 0.0;
+        }
+
+        distance_squared / (cosine * self.area)
+    }
+
+    /// Samples a point uniformly over the quad's area.
+    fn random(&self, origin: Point3) -> Vec3 {
+        let p = self.corner + self.u * utils::random() + self.v * utils::random();
+        p - origin
+    }
+}
+
+/// The six faces of the box with opposite corners `a` and `b`, with
+/// outward-facing normals.
+pub fn box_quads(a: Point3, b: Point3, material: MaterialKind) -> [Quad; 6] {
+    let min = Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+    let max = Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+
+    let dx = Vec3::new(max.x - min.x, 0.0, 0.0);
+    let dy = Vec3::new(0.0, max.y - min.y, 0.0);
+    let dz = Vec3::new(0.0, 0.0, max.z - min.z);
+    let quad = |corner, u, v| Quad::new(corner, u, v, material.clone());
+
+    [
+        quad(Point3::new(min.x, min.y, max.z), dx, dy),
+        quad(Point3::new(max.x, min.y, max.z), -dz, dy),
+        quad(Point3::new(max.x, min.y, min.z), -dx, dy),
+        quad(Point3::new(min.x, min.y, min.z), dz, dy),
+        quad(Point3::new(min.x, max.y, max.z), dx, -dz),
+        quad(Point3::new(min.x, min.y, min.z), dx, dz),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{imaging::material::Lambertian, math::vec3::Color};
+
+    fn gray() -> MaterialKind {
+        MaterialKind::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+    }
+
+    fn any_bounds() -> Interval {
+        Interval::new(0.001, f64::INFINITY)
+    }
+
+    #[test]
+    fn test_hit_inside_and_uv() {
+        let quad = Quad::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 4.0, 0.0),
+            gray(),
+        );
+        let ray = Ray::new(Point3::new(0.5, 3.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let rec = quad
+            .hit(&ray, any_bounds())
+            .expect("ray should hit the quad");
+
+        assert!((rec.t - 1.0).abs() < 1e-12);
+        assert!((rec.u - 0.25).abs() < 1e-12);
+        assert!((rec.v - 0.75).abs() < 1e-12);
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+
+        let outside = Ray::new(Point3::new(2.5, 1.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(quad.hit(&outside, any_bounds()).is_none());
+        let parallel = Ray::new(Point3::new(0.5, 1.0, 1.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(quad.hit(&parallel, any_bounds()).is_none());
+    }
+
+    #[test]
+    fn test_pdf_integrates_to_one() {
+        let quad = Quad::new(
+            Point3::new(-0.5, 1.0, -0.5),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            gray(),
+        );
+        let origin = Point3::new(0.0, 0.0, 0.0);
+
+        // Uniform sphere sampling estimates the integral of the pdf.
+        utils::reseed(5);
+        let n = 200_000;
+        let sum: f64 = (0..n)
+            .map(|_| quad.pdf_value(origin, Vec3::random_unit_vector()))
+            .sum();
+        let integral = sum / n as f64 * 4.0 * std::f64::consts::PI;
+        assert!((integral - 1.0).abs() < 0.03, "{integral}");
+    }
+
+    #[test]
+    fn test_box_normals_face_outwards() {
+        let center = Point3::new(0.5, 1.0, 1.5);
+        for face in box_quads(
+            Point3::new(1.0, 2.0, 3.0),
+            Point3::new(0.0, 0.0, 0.0),
+            gray(),
+        ) {
+            let face_center = face.corner + (face.u + face.v) * 0.5;
+            assert!(face.normal.dot(face_center - center) > 0.0);
+        }
+    }
+}
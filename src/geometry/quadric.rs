@@ -0,0 +1,621 @@
+use std::f64::consts::PI;
+
+use crate::{
+    geometry::{
+        hittable::{HitRecord, Hittable},
+        pdf::area_pdf,
+    },
+    imaging::material::MaterialKind,
+    math::{
+        aabb::Aabb,
+        interval::Interval,
+        onb::Onb,
+        polynomial::solve_quadratic,
+        ray::Ray,
+        utils,
+        vec3::{Point3, Vec3},
+    },
+};
+
+/// Local coordinates around a segment from `base` along `axis`: the segment
+/// runs along local z from 0 to `height`.
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    base: Point3,
+    axes: Onb,
+    height: f64,
+}
+
+impl Frame {
+    fn new(base: Point3, axis: Vec3) -> Self {
+        Self {
+            base,
+            axes: Onb::new(axis),
+            height: axis.length(),
+        }
+    }
+
+    fn local_vector(&self, v: Vec3) -> Vec3 {
+        Vec3::new(v.dot(self.axes.u), v.dot(self.axes.v), v.dot(self.axes.w))
+    }
+
+    fn ray_to_local(&self, ray: &Ray) -> (Vec3, Vec3) {
+        (
+            self.local_vector(ray.origin - self.base),
+            self.local_vector(ray.direction),
+        )
+    }
+
+    fn point_to_world(&self, p: Vec3) -> Point3 {
+        self.base + self.axes.transform(p)
+    }
+
+    /// Box around a disk of `radius` centered on the axis at height `z`.
+    fn disk_box(&self, z: f64, radius: f64) -> Aabb {
+        let n = self.axes.w;
+        let extent = Vec3::new(
+            radius * (1.0 - n.x * n.x).max(0.0).sqrt(),
+            radius * (1.0 - n.y * n.y).max(0.0).sqrt(),
+            radius * (1.0 - n.z * n.z).max(0.0).sqrt(),
+        );
+        let center = self.base + n * z;
+        Aabb::from_points(center - extent, center + extent)
+    }
+}
+
+/// A hit in local coordinates, before it is turned into a [`HitRecord`].
+struct LocalHit {
+    t: f64,
+    normal: Vec3,
+    uv: (f64, f64),
+}
+
+/// Angle around the local z axis, mapped to [0, 1].
+fn angle_u(p: Vec3) -> f64 {
+    (p.y.atan2(p.x) + PI) / (2.0 * PI)
+}
+
+/// Lateral surface of a cone frustum whose radius changes linearly from
+/// `r0` at z = 0 to `r1` at z = `height`; the z range is not checked.
+/// Returns both roots of `x² + y² = r(z)²` in ascending order.
+fn frustum_roots(o: Vec3, d: Vec3, r0: f64, r1: f64, height: f64) -> Option<(f64, f64)> {
+    let k = (r1 - r0) / height;
+    let r_o = r0 + k * o.z;
+    solve_quadratic(
+        d.x * d.x + d.y * d.y - k * k * d.z * d.z,
+        2.0 * (o.x * d.x + o.y * d.y - k * r_o * d.z),
+        o.x * o.x + o.y * o.y - r_o * r_o,
+    )
+}
+
+/// Outward normal of the frustum side at local point `p`.
+fn frustum_normal(p: Vec3, r0: f64, r1: f64, height: f64) -> Vec3 {
+    let k = (r1 - r0) / height;
+    Vec3::new(p.x, p.y, -k * (r0 + k * p.z)).unit_vector()
+}
+
+/// Roots of a sphere of `radius` centered on the axis at height `z`.
+fn sphere_roots(o: Vec3, d: Vec3, z: f64, radius: f64) -> Option<(f64, f64)> {
+    let oc = o - Vec3::new(0.0, 0.0, z);
+    solve_quadratic(
+        d.length_squared(),
+        2.0 * oc.dot(d),
+        oc.length_squared() - radius * radius,
+    )
+}
+
+/// Hit on a cap disk of `radius` at height `z`, facing `sign` along z.
+fn cap_hit(o: Vec3, d: Vec3, z: f64, radius: f64, sign: f64, bounds: Interval) -> Option<LocalHit> {
+    if radius <= 0.0 || d.z == 0.0 {
+        return None;
+    }
+    let t = (z - o.z) / d.z;
+    if !bounds.surrounds(t) {
+        return None;
+    }
+    let p = o + d * t;
+    let r2 = p.x * p.x + p.y * p.y;
+    (r2 <= radius * radius).then(|| LocalHit {
+        t,
+        normal: Vec3::new(0.0, 0.0, sign),
+        uv: (angle_u(p), r2.sqrt() / radius),
+    })
+}
+
+/// A truncated cone with end radii `r0` and `r1`, shared by [`Cylinder`]
+/// and [`Cone`].
+struct Frustum {
+    frame: Frame,
+    r0: f64,
+    r1: f64,
+    capped: bool,
+    material: MaterialKind,
+}
+
+impl Frustum {
+    fn side_area(&self) -> f64 {
+        let slant = (self.frame.height.powi(2) + (self.r1 - self.r0).powi(2)).sqrt();
+        PI * (self.r0 + self.r1) * slant
+    }
+
+    fn cap_area(&self) -> f64 {
+        if self.capped {
+            PI * (self.r0 * self.r0 + self.r1 * self.r1)
+        } else {
+            0.0
+        }
+    }
+
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
+        let Frame { height, .. } = self.frame;
+        let (o, d) = self.frame.ray_to_local(ray);
+        let mut bounds = bounds;
+        let mut closest = None;
+
+        if let Some((t0, t1)) = frustum_roots(o, d, self.r0, self.r1, height) {
+            for t in [t0, t1] {
+                let p = o + d * t;
+                if bounds.surrounds(t) && (0.0..=height).contains(&p.z) {
+                    bounds.max = t;
+                    closest = Some(LocalHit {
+                        t,
+                        normal: frustum_normal(p, self.r0, self.r1, height),
+                        uv: (angle_u(p), p.z / height),
+                    });
+                    break;
+                }
+            }
+        }
+
+        if self.capped {
+            for (z, radius, sign) in [(0.0, self.r0, -1.0), (height, self.r1, 1.0)] {
+                if let Some(hit) = cap_hit(o, d, z, radius, sign, bounds) {
+                    bounds.max = hit.t;
+                    closest = Some(hit);
+                }
+            }
+        }
+
+        closest.map(|hit| {
+            HitRecord::new(
+                ray.at(hit.t),
+                self.frame.axes.transform(hit.normal),
+                hit.t,
+                hit.uv,
+                ray,
+                &self.material,
+            )
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::surrounding(
+            self.frame.disk_box(0.0, self.r0),
+            self.frame.disk_box(self.frame.height, self.r1),
+        )
+    }
+
+    /// Samples a point uniformly over the side and caps.
+    fn random_point(&self) -> Point3 {
+        let Frame { height, .. } = self.frame;
+        let side = self.side_area();
+        let pick = utils::random() * (side + self.cap_area());
+
+        let local = if pick < side {
+            // The side's area grows linearly with the radius, so r² is
+            // uniform between the end radii.
+            let r = (self.r0 * self.r0 + utils::random() * (self.r1 * self.r1 - self.r0 * self.r0))
+                .sqrt();
+            let z = if self.r0 == self.r1 {
+                utils::random() * height
+            } else {
+                (r - self.r0) / (self.r1 - self.r0) * height
+            };
+            let phi = 2.0 * PI * utils::random();
+            Vec3::new(r * phi.cos(), r * phi.sin(), z)
+        } else {
+            let bottom = PI * self.r0 * self.r0;
+            let (z, radius) = if pick - side < bottom {
+                (0.0, self.r0)
+            } else {
+                (height, self.r1)
+            };
+            Vec3::random_in_unit_disk() * radius + Vec3::new(0.0, 0.0, z)
+        };
+        self.frame.point_to_world(local)
+    }
+}
+
+/// A cylinder of `radius` around the segment from `base` to `base + axis`.
+/// On the side, u runs around the axis and v along it; the caps use polar
+/// coordinates.
+pub struct Cylinder {
+    shape: Frustum,
+}
+
+impl Cylinder {
+    pub fn new(base: Point3, axis: Vec3, radius: f64, material: MaterialKind) -> Self {
+        Self {
+            shape: Frustum {
+                frame: Frame::new(base, axis),
+                r0: radius,
+                r1: radius,
+                capped: true,
+                material,
+            },
+        }
+    }
+
+    /// Closes (the default) or opens both ends.
+    pub fn with_caps(mut self, capped: bool) -> Self {
+        self.shape.capped = capped;
+        self
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
+        self.shape.hit(ray, bounds)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.shape.bounding_box()
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.shape.material.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        let area = self.shape.side_area() + self.shape.cap_area();
+        area_pdf(self, area, origin, direction)
+    }
+
+    fn random(&self, origin: Point3) -> Vec3 {
+        self.shape.random_point() - origin
+    }
+}
+
+/// A cone, or truncated cone, along the segment from `base` to
+/// `base + axis`, with radius `base_radius` at the base and `top_radius`
+/// at the top (0 for a pointed cone). Texture coordinates are as for
+/// [`Cylinder`].
+pub struct Cone {
+    shape: Frustum,
+}
+
+impl Cone {
+    pub fn new(
+        base: Point3,
+        axis: Vec3,
+        base_radius: f64,
+        top_radius: f64,
+        material: MaterialKind,
+    ) -> Self {
+        Self {
+            shape: Frustum {
+                frame: Frame::new(base, axis),
+                r0: base_radius,
+                r1: top_radius,
+                capped: true,
+                material,
+            },
+        }
+    }
+
+    /// Closes (the default) or opens both ends.
+    pub fn with_caps(mut self, capped: bool) -> Self {
+        self.shape.capped = capped;
+        self
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
+        self.shape.hit(ray, bounds)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.shape.bounding_box()
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.shape.material.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        let area = self.shape.side_area() + self.shape.cap_area();
+        area_pdf(self, area, origin, direction)
+    }
+
+    fn random(&self, origin: Point3) -> Vec3 {
+        self.shape.random_point() - origin
+    }
+}
+
+/// A cylinder of `radius` around the segment from `base` to `base + axis`,
+/// closed by hemispheres. u runs around the axis and v along the whole
+/// length, ends included.
+pub struct Capsule {
+    frame: Frame,
+    radius: f64,
+    material: MaterialKind,
+}
+
+impl Capsule {
+    pub fn new(base: Point3, axis: Vec3, radius: f64, material: MaterialKind) -> Self {
+        Self {
+            frame: Frame::new(base, axis),
+            radius,
+            material,
+        }
+    }
+
+    fn side_area(&self) -> f64 {
+        2.0 * PI * self.radius * self.frame.height
+    }
+
+    fn area(&self) -> f64 {
+        self.side_area() + 4.0 * PI * self.radius * self.radius
+    }
+}
+
+impl Hittable for Capsule {
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
+        let (radius, height) = (self.radius, self.frame.height);
+        let (o, d) = self.frame.ray_to_local(ray);
+        let mut bounds = bounds;
+        let mut closest: Option<(f64, Vec3, Vec3)> = None;
+
+        // Each part only counts over the z range where it forms the
+        // capsule's surface.
+        let parts = [
+            (
+                frustum_roots(o, d, radius, radius, height),
+                None,
+                0.0,
+                height,
+            ),
+            (
+                sphere_roots(o, d, 0.0, radius),
+                Some(0.0),
+                f64::NEG_INFINITY,
+                0.0,
+            ),
+            (
+                sphere_roots(o, d, height, radius),
+                Some(height),
+                height,
+                f64::INFINITY,
+            ),
+        ];
+        for (roots, sphere_z, z_min, z_max) in parts {
+            let Some((t0, t1)) = roots else {
+                continue;
+            };
+            for t in [t0, t1] {
+                let p = o + d * t;
+                if bounds.surrounds(t) && (z_min..=z_max).contains(&p.z) {
+                    let center = Vec3::new(0.0, 0.0, sphere_z.unwrap_or(p.z));
+                    bounds.max = t;
+                    closest = Some((t, p, (p - center) / radius));
+                    break;
+                }
+            }
+        }
+
+        let (t, p, normal) = closest?;
+        let uv = (angle_u(p), (p.z + radius) / (height + 2.0 * radius));
+        Some(HitRecord::new(
+            ray.at(t),
+            self.frame.axes.transform(normal),
+            t,
+            uv,
+            ray,
+            &self.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        let top = self
+            .frame
+            .point_to_world(Vec3::new(0.0, 0.0, self.frame.height));
+        Aabb::surrounding(
+            Aabb::from_points(self.frame.base - r, self.frame.base + r),
+            Aabb::from_points(top - r, top + r),
+        )
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        area_pdf(self, self.area(), origin, direction)
+    }
+
+    /// Samples a point uniformly over the side and both hemispheres.
+    fn random(&self, origin: Point3) -> Vec3 {
+        let (radius, height) = (self.radius, self.frame.height);
+        let local = if utils::random() * self.area() < self.side_area() {
+            let phi = 2.0 * PI * utils::random();
+            Vec3::new(
+                radius * phi.cos(),
+                radius * phi.sin(),
+                utils::random() * height,
+            )
+        } else {
+            // A point on a whole sphere, folded onto the end it is nearer.
+            let s = Vec3::random_unit_vector() * radius;
+            if s.z < 0.0 {
+                s
+            } else {
+                s + Vec3::new(0.0, 0.0, height)
+            }
+        };
+        self.frame.point_to_world(local) - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{imaging::material::Lambertian, math::vec3::Color};
+
+    fn gray() -> MaterialKind {
+        MaterialKind::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+    }
+
+    fn any_bounds() -> Interval {
+        Interval::new(0.001, f64::INFINITY)
+    }
+
+    fn tilted_axis() -> Vec3 {
+        Vec3::new(0.4, 1.5, -0.3)
+    }
+
+    fn shapes() -> Vec<(&'static str, Box<dyn Hittable>)> {
+        let base = Point3::new(0.2, -0.5, 0.1);
+        vec![
+            (
+                "cylinder",
+                Box::new(Cylinder::new(base, tilted_axis(), 0.5, gray())),
+            ),
+            (
+                "open cylinder",
+                Box::new(Cylinder::new(base, tilted_axis(), 0.5, gray()).with_caps(false)),
+            ),
+            (
+                "cone",
+                Box::new(Cone::new(base, tilted_axis(), 0.6, 0.0, gray())),
+            ),
+            (
+                "truncated cone",
+                Box::new(Cone::new(base, tilted_axis(), 0.3, 0.7, gray())),
+            ),
+            (
+                "capsule",
+                Box::new(Capsule::new(base, tilted_axis(), 0.4, gray())),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_cylinder_side_and_caps() {
+        let cylinder = Cylinder::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            1.0,
+            gray(),
+        );
+
+        let side = Ray::new(Point3::new(5.0, 0.5, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+        let rec = cylinder.hit(&side, any_bounds()).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-9);
+        assert!((rec.normal - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-9);
+        assert!((rec.v - 0.25).abs() < 1e-9);
+
+        let top = Ray::new(Point3::new(0.5, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let rec = cylinder.hit(&top, any_bounds()).unwrap();
+        assert!((rec.t - 3.0).abs() < 1e-9);
+        assert!((rec.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+
+        // Without caps, the ray passes down the inside and out the bottom.
+        let open = Cylinder::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            1.0,
+            gray(),
+        )
+        .with_caps(false);
+        assert!(open.hit(&top, any_bounds()).is_none());
+    }
+
+    #[test]
+    fn test_cone_normal_leans_towards_apex() {
+        let cone = Cone::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            1.0,
+            0.0,
+            gray(),
+        );
+        let ray = Ray::new(Point3::new(5.0, 0.5, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+        let rec = cone.hit(&ray, any_bounds()).unwrap();
+        assert!((rec.p.x - 0.5).abs() < 1e-9);
+        let expected = Vec3::new(1.0, 1.0, 0.0).unit_vector();
+        assert!((rec.normal - expected).length() < 1e-9);
+    }
+
+    #[test]
+    fn test_capsule_ends_are_round() {
+        let capsule = Capsule::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            0.5,
+            gray(),
+        );
+        let ray = Ray::new(Point3::new(0.3, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let rec = capsule.hit(&ray, any_bounds()).unwrap();
+        let expected_y = 2.0 + (0.25f64 - 0.09).sqrt();
+        assert!((rec.p.y - expected_y).abs() < 1e-9);
+        assert!((rec.normal - (rec.p - Vec3::new(0.0, 2.0, 0.0)) / 0.5).length() < 1e-9);
+    }
+
+    #[test]
+    fn test_hits_lie_inside_bounding_box() {
+        utils::reseed(3);
+        for (name, shape) in shapes() {
+            let bbox = shape.bounding_box();
+            let mut hits = 0;
+            for _ in 0..2000 {
+                let origin = Vec3::random_range(-3.0, 3.0);
+                let target = bbox.centroid() + Vec3::random_range(-0.5, 0.5);
+                let ray = Ray::new(origin, target - origin);
+                if let Some(rec) = shape.hit(&ray, any_bounds()) {
+                    hits += 1;
+                    assert!(bbox.contains(rec.p), "{name}: {}", rec.p);
+                    assert!((rec.normal.length() - 1.0).abs() < 1e-9, "{name}");
+                    assert!((0.0..=1.0).contains(&rec.u), "{name}");
+                    assert!((0.0..=1.0).contains(&rec.v), "{name}");
+                }
+            }
+            assert!(hits > 100, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_sampled_points_lie_on_surface() {
+        utils::reseed(4);
+        for (name, shape) in shapes() {
+            let origin = Point3::new(4.0, 1.0, 3.0);
+            for _ in 0..200 {
+                // One of the shape's crossings along the ray towards the
+                // sample is the sample itself.
+                let ray = Ray::new(origin, shape.random(origin));
+                let mut bounds = any_bounds();
+                let mut found = false;
+                while let Some(rec) = shape.hit(&ray, bounds) {
+                    found |= (rec.t - 1.0).abs() < 1e-6;
+                    bounds.min = rec.t + 1e-9;
+                }
+                assert!(found, "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_pdf_integrates_to_one() {
+        utils::reseed(5);
+        for (name, shape) in shapes() {
+            let origin = Point3::new(3.0, 2.0, -2.0);
+            let n = 100_000;
+            let sum: f64 = (0..n)
+                .map(|_| shape.pdf_value(origin, Vec3::random_unit_vector()))
+                .sum();
+            let integral = sum / n as f64 * 4.0 * PI;
+            assert!((integral - 1.0).abs() < 0.05, "{name}: {integral}");
+        }
+    }
+}
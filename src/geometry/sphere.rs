@@ -1,27 +1,49 @@
 use crate::{
     geometry::hittable::{HitRecord, Hittable},
     imaging::material::MaterialKind,
-    math::{interval::Interval, ray::Ray, vec3::Vec3},
+    math::{
+        aabb::Aabb,
+        interval::Interval,
+        onb::Onb,
+        ray::Ray,
+        utils,
+        vec3::{Point3, Vec3},
+    },
 };
+use std::f64::consts::PI;
 
 pub struct Sphere {
     center: Vec3,
     radius: f64,
     material: MaterialKind,
+    bbox: Aabb,
 }
 
 impl Sphere {
     pub fn new(center: Vec3, radius: f64, material: MaterialKind) -> Self {
+        let rvec = Vec3::new(radius, radius, radius);
+        let bbox = Aabb::from_points(center - rvec, center + rvec);
+
         Self {
             center,
             radius,
             material,
+            bbox,
         }
     }
+
+    /// Maps a point on the unit sphere to (u, v): u is the angle around the
+    /// y axis starting from -x, v the angle from -y to +y, both in [0, 1].
+    fn uv(p: Vec3) -> (f64, f64) {
+        let theta = (-p.y).acos();
+        let phi = (-p.z).atan2(p.x) + PI;
+
+        (phi / (2.0 * PI), theta / PI)
+    }
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
         let oc: Vec3 = self.center - ray.origin;
         let a: f64 = ray.direction.dot(ray.direction);
         let h: f64 = oc.dot(ray.direction);
@@ -46,6 +68,124 @@ impl Hittable for Sphere {
         let p = ray.at(root);
         let normal = (p - self.center) / self.radius;
 
-        Some(HitRecord::new(p, normal, root, ray, self.material.clone()))
+        Some(HitRecord::new(
+            p,
+            normal,
+            root,
+            Self::uv(normal),
+            ray,
+            &self.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        if self
+            .hit(
+                &Ray::new(origin, direction),
+                Interval::new(0.001, f64::INFINITY),
+            )
+            .is_none()
+        {
+            return 0.0;
+        }
+
+        let distance_squared = (self.center - origin).length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return 0.0;
+        }
+
+        let cos_theta_max = (1.0 - radius_squared / distance_squared).sqrt();
+        let solid_angle = 2.0 * PI * (1.0 - cos_theta_max);
+
+        1.0 / solid_angle
+    }
+
+    /// Samples the cone of directions subtended by the sphere.
+    fn random(&self, origin: Point3) -> Vec3 {
+        let direction = self.center - origin;
+        let distance_squared = direction.length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return Vec3::random_unit_vector();
+        }
+
+        let r1 = utils::random();
+        let r2 = utils::random();
+        let z = 1.0 + r2 * ((1.0 - radius_squared / distance_squared).sqrt() - 1.0);
+        let phi = 2.0 * PI * r1;
+        let sin_theta = (1.0 - z * z).sqrt();
+
+        Onb::new(direction).transform(Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{imaging::material::DiffuseLight, math::vec3::Color};
+
+    fn lamp() -> Sphere {
+        let material = MaterialKind::DiffuseLight(DiffuseLight::new(Color::new(4.0, 4.0, 4.0)));
+        Sphere::new(Point3::new(0.5, 1.0, -2.0), 0.75, material)
+    }
+
+    #[test]
+    fn test_pdf_integrates_to_one() {
+        let sphere = lamp();
+        let origin = Point3::new(0.0, 0.0, 1.0);
+        utils::reseed(5);
+        let n = 200_000;
+        let sum: f64 = (0..n)
+            .map(|_| sphere.pdf_value(origin, Vec3::random_unit_vector()))
+            .sum();
+        let integral = sum / n as f64 * 4.0 * PI;
+        assert!((integral - 1.0).abs() < 0.03, "{integral}");
+    }
+
+    #[test]
+    fn test_sampled_directions_hit_the_sphere() {
+        let sphere = lamp();
+        let origin = Point3::new(0.0, 0.0, 1.0);
+        utils::reseed(9);
+        for _ in 0..1000 {
+            let direction = sphere.random(origin);
+            let ray = Ray::new(origin, direction);
+            assert!(
+                sphere
+                    .hit(&ray, Interval::new(0.001, f64::INFINITY))
+                    .is_some()
+            );
+            assert!(sphere.pdf_value(origin, direction) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_uv_at_known_points() {
+        let sphere = Sphere::new(Point3::new(1.0, 2.0, 3.0), 2.0, lamp().material);
+        for (direction, (u, v)) in [
+            (Vec3::new(1.0, 0.0, 0.0), (0.5, 0.5)),
+            (Vec3::new(-1.0, 0.0, 0.0), (0.0, 0.5)),
+            (Vec3::new(0.0, 0.0, 1.0), (0.25, 0.5)),
+            (Vec3::new(0.0, 0.0, -1.0), (0.75, 0.5)),
+            (Vec3::new(0.0, 1.0, 0.0), (0.5, 1.0)),
+            (Vec3::new(0.0, -1.0, 0.0), (0.5, 0.0)),
+        ] {
+            // Shoot at the sphere from outside along `direction`.
+            let ray = Ray::new(sphere.center + direction * 5.0, -direction);
+            let rec = sphere
+                .hit(&ray, Interval::new(0.001, f64::INFINITY))
+                .expect("ray should hit the sphere");
+            assert!((rec.u - u).abs() < 1e-9, "{direction}: u = {}", rec.u);
+            assert!((rec.v - v).abs() < 1e-9, "{direction}: v = {}", rec.v);
+        }
     }
 }
@@ -0,0 +1,115 @@
+use crate::{
+    geometry::hittable::{HitRecord, Hittable},
+    math::{
+        aabb::Aabb,
+        interval::Interval,
+        ray::Ray,
+        vec3::{Point3, Vec3},
+    },
+};
+
+/// IDs stamped on the hit records of a [`Tagged`] object. An ID of 0
+/// leaves whatever the wrapped object set, so tags can be nested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Tag {
+    pub object: u32,
+    pub material: u32,
+}
+
+impl Tag {
+    pub fn new(object: u32, material: u32) -> Self {
+        Self { object, material }
+    }
+}
+
+/// Labels every hit on the wrapped object with its IDs, so that renders can
+/// tell objects and materials apart (e.g. for ID mattes).
+pub struct Tagged<H: Hittable> {
+    object: H,
+    tag: Tag,
+}
+
+impl<H: Hittable> Tagged<H> {
+    pub fn new(object: H, tag: Tag) -> Self {
+        Self { object, tag }
+    }
+}
+
+impl<H: Hittable> Hittable for Tagged<H> {
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
+        let mut rec = self.object.hit(ray, bounds)?;
+        if self.tag.object != 0 {
+            rec.object_id = self.tag.object;
+        }
+        if self.tag.material != 0 {
+            rec.material_id = self.tag.material;
+        }
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.object.bounding_box()
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.object.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        self.object.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: Point3) -> Vec3 {
+        self.object.random(origin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geometry::sphere::Sphere,
+        imaging::material::{Lambertian, MaterialKind},
+        math::vec3::Color,
+    };
+
+    fn sphere() -> Sphere {
+        Sphere::new(
+            Vec3::new(0.0, 0.0, -2.0),
+            0.5,
+            MaterialKind::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    fn hit_ids(object: &impl Hittable) -> (u32, u32) {
+        let ray = Ray::new(Vec3::default(), Vec3::new(0.0, 0.0, -1.0));
+        let rec = object
+            .hit(&ray, Interval::new(0.001, f64::INFINITY))
+            .unwrap();
+        (rec.object_id, rec.material_id)
+    }
+
+    #[test]
+    fn test_one_tag_stamps_both_ids() {
+        let tagged = Tagged::new(sphere(), Tag::new(7, 3));
+        assert_eq!(hit_ids(&tagged), (7, 3));
+
+        let miss = Ray::new(Vec3::default(), Vec3::new(0.0, 1.0, 0.0));
+        assert!(
+            tagged
+                .hit(&miss, Interval::new(0.001, f64::INFINITY))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_tags_are_kept_through_nesting() {
+        // Shared geometry keeps its material IDs when placed as an object.
+        let tagged = Tagged::new(Tagged::new(sphere(), Tag::new(0, 3)), Tag::new(7, 0));
+        assert_eq!(hit_ids(&tagged), (7, 3));
+
+        // The outermost non-zero ID wins.
+        let tagged = Tagged::new(Tagged::new(sphere(), Tag::new(2, 3)), Tag::new(7, 0));
+        assert_eq!(hit_ids(&tagged), (7, 3));
+    }
+}
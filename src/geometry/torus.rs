@@ -0,0 +1,248 @@
+use std::f64::consts::PI;
+
+use crate::{
+    geometry::{
+        hittable::{HitRecord, Hittable},
+        pdf::area_pdf,
+    },
+    imaging::material::MaterialKind,
+    math::{
+        aabb::Aabb,
+        interval::Interval,
+        onb::Onb,
+        polynomial::solve_quartic,
+        ray::Ray,
+        utils,
+        vec3::{Point3, Vec3},
+    },
+};
+
+/// A ring-shaped tube of `minor_radius` swept around a circle of
+/// `major_radius` centered on `center`, facing `axis`.
+///
+/// u is the angle around the axis and v the angle around the tube, both
+/// mapped to [0, 1].
+pub struct Torus {
+    center: Point3,
+    axes: Onb,
+    major_radius: f64,
+    minor_radius: f64,
+    material: MaterialKind,
+}
+
+impl Torus {
+    pub fn new(
+        center: Point3,
+        axis: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+        material: MaterialKind,
+    ) -> Self {
+        Self {
+            center,
+            axes: Onb::new(axis),
+            major_radius,
+            minor_radius,
+            material,
+        }
+    }
+
+    fn local_vector(&self, v: Vec3) -> Vec3 {
+        Vec3::new(v.dot(self.axes.u), v.dot(self.axes.v), v.dot(self.axes.w))
+    }
+
+    fn area(&self) -> f64 {
+        4.0 * PI * PI * self.major_radius * self.minor_radius
+    }
+
+    /// Outward normal at local point `p`: away from the nearest point of
+    /// the center circle.
+    fn local_normal(&self, p: Vec3) -> Vec3 {
+        let radial = Vec3::new(p.x, p.y, 0.0);
+        let ring_distance = radial.length();
+        if ring_distance == 0.0 {
+            return p.unit_vector();
+        }
+        (p - radial * (self.major_radius / ring_distance)).unit_vector()
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
+        let (big_r, small_r) = (self.major_radius, self.minor_radius);
+        let o = self.local_vector(ray.origin - self.center);
+        let d = self.local_vector(ray.direction);
+        let speed = d.length();
+        let dir = d / speed;
+
+        // Solve from the point of the ray nearest the center, so that the
+        // quartic's coefficients stay of the torus's own scale however far
+        // away the ray starts. Rays missing the bounding sphere are out.
+        let closest = -o.dot(dir);
+        let o = o + dir * closest;
+        let outer = big_r + small_r;
+        if o.length_squared() > outer * outer {
+            return None;
+        }
+
+        // (|p|² + R² - r²)² = 4R² (x² + y²) along p = o + s·dir.
+        let f = o.dot(dir);
+        let g = o.length_squared() + big_r * big_r - small_r * small_r;
+        let four_r2 = 4.0 * big_r * big_r;
+        let roots = solve_quartic(
+            1.0,
+            4.0 * f,
+            4.0 * f * f + 2.0 * g - four_r2 * (dir.x * dir.x + dir.y * dir.y),
+            4.0 * f * g - 2.0 * four_r2 * (o.x * dir.x + o.y * dir.y),
+            g * g - four_r2 * (o.x * o.x + o.y * o.y),
+        );
+
+        let (s, t) = roots
+            .as_slice()
+            .iter()
+            .map(|&s| (s, (s + closest) / speed))
+            .find(|&(_, t)| bounds.surrounds(t))?;
+
+        let p = o + dir * s;
+        let radial = (p.x * p.x + p.y * p.y).sqrt();
+        let uv = (
+            (p.y.atan2(p.x) + PI) / (2.0 * PI),
+            (p.z.atan2(radial - big_r) + PI) / (2.0 * PI),
+        );
+
+        Some(HitRecord::new(
+            ray.at(t),
+            self.axes.transform(self.local_normal(p)),
+            t,
+            uv,
+            ray,
+            &self.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // The center circle's box, grown by the tube radius.
+        let n = self.axes.w;
+        let r = self.minor_radius;
+        let extent = Vec3::new(
+            self.major_radius * (1.0 - n.x * n.x).max(0.0).sqrt() + r,
+            self.major_radius * (1.0 - n.y * n.y).max(0.0).sqrt() + r,
+            self.major_radius * (1.0 - n.z * n.z).max(0.0).sqrt() + r,
+        );
+        Aabb::from_points(self.center - extent, self.center + extent)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        area_pdf(self, self.area(), origin, direction)
+    }
+
+    /// Samples a point uniformly over the surface. The outer side of the
+    /// tube holds more area than the inner, so the tube angle is drawn by
+    /// rejection against the local circumference.
+    fn random(&self, origin: Point3) -> Vec3 {
+        let (big_r, small_r) = (self.major_radius, self.minor_radius);
+        let theta = loop {
+            let theta = 2.0 * PI * utils::random();
+            if utils::random() * (big_r + small_r) <= big_r + small_r * theta.cos() {
+                break theta;
+            }
+        };
+        let phi = 2.0 * PI * utils::random();
+        let ring = big_r + small_r * theta.cos();
+        let local = Vec3::new(ring * phi.cos(), ring * phi.sin(), small_r * theta.sin());
+        self.center + self.axes.transform(local) - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{imaging::material::Lambertian, math::vec3::Color};
+
+    fn tilted_torus() -> Torus {
+        let material = MaterialKind::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        Torus::new(
+            Point3::new(0.3, -0.2, 0.1),
+            Vec3::new(0.4, 1.0, -0.3),
+            1.0,
+            0.3,
+            material,
+        )
+    }
+
+    /// Distance from `p` to the tube's center circle.
+    fn tube_distance(torus: &Torus, p: Point3) -> f64 {
+        let local = torus.local_vector(p - torus.center);
+        let radial = (local.x * local.x + local.y * local.y).sqrt();
+        Vec3::new(radial - torus.major_radius, 0.0, local.z).length()
+    }
+
+    #[test]
+    fn test_hits_lie_on_the_surface() {
+        let torus = tilted_torus();
+        let bbox = torus.bounding_box();
+        utils::reseed(4);
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let origin = Point3::random_unit_vector() * 4.0;
+            let target = torus.center + Vec3::random_unit_vector() * 1.3;
+            let ray = Ray::new(origin, target - origin);
+            if let Some(rec) = torus.hit(&ray, Interval::new(0.001, f64::INFINITY)) {
+                hits += 1;
+                assert!((tube_distance(&torus, rec.p) - 0.3).abs() < 1e-9);
+                assert!((rec.normal.length() - 1.0).abs() < 1e-9);
+                assert!(bbox.contains(rec.p));
+                assert!((0.0..=1.0).contains(&rec.u) && (0.0..=1.0).contains(&rec.v));
+            }
+        }
+        assert!(hits > 500, "{hits}");
+    }
+
+    #[test]
+    fn test_hole_and_distant_rays() {
+        let material = MaterialKind::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let torus = Torus::new(
+            Point3::default(),
+            Vec3::new(0.0, 1.0, 0.0),
+            1.0,
+            0.25,
+            material,
+        );
+        let bounds = Interval::new(0.001, f64::INFINITY);
+
+        // Straight down through the hole.
+        let through = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(torus.hit(&through, bounds).is_none());
+
+        // From far away along x: the near side of the tube is at x = 1.25.
+        let far = Ray::new(Point3::new(1e6, 0.0, 0.0), Vec3::new(-2.0, 0.0, 0.0));
+        let rec = torus.hit(&far, bounds).expect("ray should hit the torus");
+        assert!((rec.p.x - 1.25).abs() < 1e-6, "{:?}", rec.p);
+        assert!((rec.normal - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-6);
+
+        // From inside the tube, the exit is the nearest hit.
+        let inside = Ray::new(Point3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let rec = torus
+            .hit(&inside, bounds)
+            .expect("ray should exit the tube");
+        assert!((rec.t - 0.25).abs() < 1e-9);
+        assert!(!rec.front_face);
+    }
+
+    #[test]
+    fn test_pdf_integrates_to_one() {
+        let torus = tilted_torus();
+        let origin = Point3::new(0.5, 2.0, 2.5);
+        utils::reseed(6);
+        let n = 200_000;
+        let sum: f64 = (0..n)
+            .map(|_| torus.pdf_value(origin, Vec3::random_unit_vector()))
+            .sum();
+        let integral = sum / n as f64 * 4.0 * PI;
+        assert!((integral - 1.0).abs() < 0.03, "{integral}");
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{
+    geometry::hittable::{HitRecord, Hittable},
+    math::{
+        aabb::Aabb,
+        interval::Interval,
+        mat4::Mat4,
+        ray::Ray,
+        vec3::{Point3, Vec3},
+    },
+};
+
+/// Places a hittable in the world with an affine transform. Rays are moved
+/// into object space for intersection and hits are moved back, so the
+/// wrapped object (often a shared `Arc`) is never copied.
+pub struct Transformed<H: Hittable> {
+    object: H,
+    object_to_world: Mat4,
+    world_to_object: Mat4,
+    /// Inverse transpose, used to carry normals back to world space.
+    normal_to_world: Mat4,
+    /// Volume scale of `world_to_object`, for converting solid-angle densities.
+    inverse_determinant: f64,
+    bbox: Aabb,
+}
+
+impl<H: Hittable> Transformed<H> {
+    /// Wraps `object`, returning `None` if `transform` is not invertible.
+    pub fn new(object: H, transform: Mat4) -> Option<Self> {
+        let world_to_object = transform.inverse()?;
+        let bbox = transform_bbox(&object.bounding_box(), &transform);
+
+        Some(Self {
+            object,
+            object_to_world: transform,
+            world_to_object,
+            normal_to_world: world_to_object.transpose(),
+            inverse_determinant: world_to_object.linear_determinant().abs(),
+            bbox,
+        })
+    }
+}
+
+impl<H: Hittable> Hittable for Transformed<H> {
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
+        // The direction is not renormalized, so `t` is the same in both spaces.
+        let local_ray = Ray::new(
+            self.world_to_object.transform_point(ray.origin),
+            self.world_to_object.transform_vector(ray.direction),
+        );
+
+        let mut rec = self.object.hit(&local_ray, bounds)?;
+        rec.p = self.object_to_world.transform_point(rec.p);
+
+        let normal = self
+            .normal_to_world
+            .transform_vector(rec.normal)
+            .unit_vector();
+        rec.normal = if normal.dot(ray.direction) > 0.0 {
+            -normal
+        } else {
+            normal
+        };
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.object.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        // Mapping unit directions through a linear map B stretches solid
+        // angle by |det B| / |B w|^3, which is 1 for rigid motions.
+        let local = self
+            .world_to_object
+            .transform_vector(direction.unit_vector());
+        let local_length = local.length();
+        let pdf = self
+            .object
+            .pdf_value(self.world_to_object.transform_point(origin), local);
+
+        pdf * self.inverse_determinant / (local_length * local_length * local_length)
+    }
+
+    fn random(&self, origin: Point3) -> Vec3 {
+        let local = self
+            .object
+            .random(self.world_to_object.transform_point(origin));
+        self.object_to_world.transform_vector(local)
+    }
+}
+
+/// Returns the box enclosing all eight transformed corners of `bbox`.
+fn transform_bbox(bbox: &Aabb, transform: &Mat4) -> Aabb {
+    if bbox.is_empty() {
+        return Aabb::EMPTY;
+    }
+    // Corners at infinity do not transform meaningfully.
+    if !bbox.is_bounded() {
+        return Aabb::UNIVERSE;
+    }
+
+    let mut result = Aabb::EMPTY;
+    for i in 0..8 {
+        let corner = Vec3::new(
+            if i & 1 == 0 { bbox.x.min } else { bbox.x.max },
+            if i & 2 == 0 { bbox.y.min } else { bbox.y.max },
+            if i & 4 == 0 { bbox.z.min } else { bbox.z.max },
+        );
+        let p = transform.transform_point(corner);
+        result = Aabb::surrounding(result, Aabb::from_points(p, p));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geometry::sphere::Sphere,
+        imaging::material::{Lambertian, MaterialKind},
+        math::{utils, vec3::Color},
+    };
+
+    fn unit_sphere() -> Sphere {
+        let material = MaterialKind::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0, material)
+    }
+
+    #[test]
+    fn test_translated_sphere() {
+        let moved = Transformed::new(unit_sphere(), Mat4::translation(Vec3::new(5.0, 0.0, 0.0)))
+            .expect("translation is invertible");
+        let ray = Ray::new(Vec3::new(5.0, 0.0, 10.0), Vec3::new(0.0, 0.0, -1.0));
+        let rec = moved
+            .hit(&ray, Interval::new(0.001, f64::INFINITY))
+            .expect("ray should hit the moved sphere");
+
+        assert!((rec.t - 9.0).abs() < 1e-9);
+        assert!((rec.p - Vec3::new(5.0, 0.0, 1.0)).length() < 1e-9);
+        assert!((rec.normal - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-9);
+        assert!(moved.bounding_box().contains(Vec3::new(5.9, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn test_scaled_sphere_normals() {
+        // Squash the sphere into an ellipsoid twice as wide as it is tall.
+        let ellipsoid =
+            Transformed::new(unit_sphere(), Mat4::scaling(Vec3::new(2.0, 1.0, 1.0))).unwrap();
+        let ray = Ray::new(Vec3::new(1.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let rec = ellipsoid
+            .hit(&ray, Interval::new(0.001, f64::INFINITY))
+            .expect("ray should hit the ellipsoid");
+
+        // Surface point (1, sqrt(3)/2, 0) has gradient (x / 4, y, z).
+        let expected = Vec3::new(0.25, 3f64.sqrt() / 2.0, 0.0).unit_vector();
+        assert!((rec.p.y - 3f64.sqrt() / 2.0).abs() < 1e-9);
+        assert!((rec.normal - expected).length() < 1e-9);
+    }
+
+    #[test]
+    fn test_light_pdf_accounts_for_scale() {
+        // The density of `random` must still integrate to one over the
+        // sphere of directions once the light is stretched.
+        let transform = Mat4::translation(Vec3::new(0.0, 0.0, -4.0))
+            * Mat4::rotation_y(30.0)
+            * Mat4::scaling(Vec3::new(3.0, 0.5, 1.0));
+        let light = Transformed::new(unit_sphere(), transform).unwrap();
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+
+        utils::reseed(2);
+        let samples = 200_000;
+        let total: f64 = (0..samples)
+            .map(|_| light.pdf_value(origin, Vec3::random_unit_vector()))
+            .sum();
+        let integral = total * 4.0 * std::f64::consts::PI / samples as f64;
+        assert!((integral - 1.0).abs() < 0.03, "integral = {integral}");
+    }
+
+    #[test]
+    fn test_singular_transform_is_rejected() {
+        assert!(Transformed::new(unit_sphere(), Mat4::scaling(Vec3::new(0.0, 1.0, 1.0))).is_none());
+    }
+}
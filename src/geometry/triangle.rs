@@ -1,7 +1,13 @@
 use crate::{
     geometry::hittable::{HitRecord, Hittable},
     imaging::material::MaterialKind,
-    math::{interval::Interval, ray::Ray, vec3::Vec3},
+    math::{
+        aabb::Aabb,
+        interval::Interval,
+        ray::Ray,
+        utils,
+        vec3::{Point3, Vec3},
+    },
 };
 
 pub struct Triangle {
@@ -9,6 +15,8 @@ pub struct Triangle {
     v1: Vec3,
     v2: Vec3,
     normal: Vec3,
+    vertex_normals: Option<[Vec3; 3]>,
+    area: f64,
     material: MaterialKind,
 }
 
@@ -16,62 +24,231 @@ impl Triangle {
     pub fn new(v0: Vec3, v1: Vec3, v2: Vec3, material: MaterialKind) -> Self {
         let edge1 = v1 - v0;
         let edge2 = v2 - v0;
-        let normal = edge1.cross(edge2).unit_vector();
+        let cross = edge1.cross(edge2);
+        let normal = cross.unit_vector();
 
         Self {
             v0,
             v1,
             v2,
             normal,
+            vertex_normals: None,
+            area: 0.5 * cross.length(),
             material,
         }
     }
+
+    /// Enables smooth shading by interpolating the given per-vertex normals.
+    pub fn with_normals(mut self, n0: Vec3, n1: Vec3, n2: Vec3) -> Self {
+        self.vertex_normals = Some([n0.unit_vector(), n1.unit_vector(), n2.unit_vector()]);
+        self
+    }
 }
 
 impl Hittable for Triangle {
-    #[allow(non_snake_case)]
-    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord> {
-        const EPSILON: f64 = 1e-8;
+    fn hit(&self, ray: &Ray, bounds: Interval) -> Option<HitRecord<'_>> {
+        let (t, u, v) = intersect(self.v0, self.v1, self.v2, ray, bounds)?;
+        let p = ray.at(t);
 
-        let E1 = self.v1 - self.v0;
-        let E2 = self.v2 - self.v0;
+        let rec = HitRecord::new(p, self.normal, t, (u, v), ray, &self.material);
+        Some(match self.vertex_normals {
+            Some(normals) => rec.with_shading_normal(interpolate_normal(normals, u, v)),
+            None => rec,
+        })
+    }
 
-        let P = ray.direction.cross(E2);
-        let det = E1.dot(P);
+    fn bounding_box(&self) -> Aabb {
+        Aabb::surrounding(
+            Aabb::from_points(self.v0, self.v1),
+            Aabb::from_points(self.v0, self.v2),
+        )
+    }
 
-        if det.abs() < EPSILON {
-            return None;
-        }
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
 
-        let inv_det = 1.0 / det;
-        let T = ray.origin - self.v0;
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        area_pdf(
+            [self.v0, self.v1, self.v2],
+            self.normal,
+            self.area,
+            origin,
+            direction,
+        )
+    }
 
-        let u = T.dot(P) * inv_det;
-        if u < 0.0 || u > 1.0 {
-            return None;
-        }
+    fn random(&self, origin: Point3) -> Vec3 {
+        sample_area(self.v0, self.v1, self.v2) - origin
+    }
+}
 
-        let Q = T.cross(E1);
-        let v = ray.direction.dot(Q) * inv_det;
+/// Möller–Trumbore ray/triangle intersection. Returns the ray parameter and
+/// the barycentric coordinates `(u, v)` of `v1` and `v2` at the hit.
+#[allow(non_snake_case)]
+pub(crate) fn intersect(
+    v0: Point3,
+    v1: Point3,
+    v2: Point3,
+    ray: &Ray,
+    bounds: Interval,
+) -> Option<(f64, f64, f64)> {
+    const EPSILON: f64 = 1e-8;
 
-        if v < 0.0 || u + v > 1.0 {
-            return None;
-        }
+    let E1 = v1 - v0;
+    let E2 = v2 - v0;
+
+    let P = ray.direction.cross(E2);
+    let det = E1.dot(P);
+
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    let T = ray.origin - v0;
+
+    let u = T.dot(P) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let Q = T.cross(E1);
+    let v = ray.direction.dot(Q) * inv_det;
+
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = E2.dot(Q) * inv_det;
+
+    if !bounds.surrounds(t) {
+        return None;
+    }
+
+    Some((t, u, v))
+}
+
+/// Blends per-vertex normals with the barycentric coordinates of a hit.
+pub(crate) fn interpolate_normal([n0, n1, n2]: [Vec3; 3], u: f64, v: f64) -> Vec3 {
+    n0 * (1.0 - u - v) + n1 * u + n2 * v
+}
+
+/// Solid-angle density of picking `direction` when sampling the triangle
+/// uniformly by area.
+pub(crate) fn area_pdf(
+    [v0, v1, v2]: [Point3; 3],
+    normal: Vec3,
+    area: f64,
+    origin: Point3,
+    direction: Vec3,
+) -> f64 {
+    let Some((t, _, _)) = intersect(
+        v0,
+        v1,
+        v2,
+        &Ray::new(origin, direction),
+        Interval::new(0.001, f64::INFINITY),
+    ) else {
+        return 0.0;
+    };
+
+    let distance_squared = t * t * direction.length_squared();
+    let cosine = (direction.dot(normal) / direction.length()).abs();
+    if cosine < 1e-8 {
+        return 0.0;
+    }
 
-        let t = E2.dot(Q) * inv_det;
+    distance_squared / (cosine * area)
+}
+
+/// Samples a point uniformly over the triangle's area.
+pub(crate) fn sample_area(v0: Point3, v1: Point3, v2: Point3) -> Point3 {
+    let su = utils::random().sqrt();
+    let r2 = utils::random();
+    v0 + (v1 - v0) * (su * (1.0 - r2)) + (v2 - v0) * (su * r2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{imaging::material::Lambertian, math::vec3::Color};
+
+    fn triangle() -> Triangle {
+        let material = MaterialKind::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        Triangle::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            material,
+        )
+    }
 
-        if !bounds.surrounds(t) {
-            return None;
+    #[test]
+    fn test_uv_is_barycentric() {
+        // With v1 on x and v2 on y, the weights of v1 and v2 are the hit's
+        // own coordinates.
+        let triangle = triangle();
+        for (x, y) in [(0.25, 0.25), (0.6, 0.1), (0.05, 0.9)] {
+            let ray = Ray::new(Vec3::new(x, y, 1.0), Vec3::new(0.0, 0.0, -1.0));
+            let rec = triangle
+                .hit(&ray, Interval::new(0.001, f64::INFINITY))
+                .expect("ray should hit the triangle");
+            assert!((rec.u - x).abs() < 1e-12 && (rec.v - y).abs() < 1e-12);
         }
+    }
 
-        let p = ray.at(t);
+    #[test]
+    fn test_pdf_integrates_to_one() {
+        let triangle = triangle();
+        let origin = Point3::new(0.2, 0.3, 0.8);
+        utils::reseed(3);
+        let n = 200_000;
+        let sum: f64 = (0..n)
+            .map(|_| triangle.pdf_value(origin, Vec3::random_unit_vector()))
+            .sum();
+        let integral = sum / n as f64 * 4.0 * std::f64::consts::PI;
+        assert!((integral - 1.0).abs() < 0.03, "{integral}");
+    }
 
-        Some(HitRecord::new(
-            p,
-            self.normal,
-            t,
-            ray,
-            self.material.clone(),
-        ))
+    fn hit_normal(triangle: &Triangle, x: f64, y: f64) -> Vec3 {
+        let ray = Ray::new(Vec3::new(x, y, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        triangle
+            .hit(&ray, Interval::new(0.001, f64::INFINITY))
+            .expect("ray should hit the triangle")
+            .normal
+    }
+
+    #[test]
+    fn test_flat_normal_faces_ray() {
+        let n = hit_normal(&triangle(), 0.25, 0.25);
+        assert_eq!(n, Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_vertex_normals_are_interpolated() {
+        let n0 = Vec3::new(0.0, 0.0, 1.0);
+        let n1 = Vec3::new(1.0, 0.0, 1.0);
+        let n2 = Vec3::new(0.0, 1.0, 1.0);
+        let smooth = triangle().with_normals(n0, n1, n2);
+
+        // Near a vertex, the shading normal approaches that vertex's normal.
+        let near_v1 = hit_normal(&smooth, 0.999, 0.0005);
+        assert!((near_v1 - n1.unit_vector()).length() < 1e-2);
+
+        let center = hit_normal(&smooth, 1.0 / 3.0, 1.0 / 3.0);
+        let expected = (n0.unit_vector() + n1.unit_vector() + n2.unit_vector()).unit_vector();
+        assert!((center - expected).length() < 1e-9);
+    }
+
+    #[test]
+    fn test_shading_normal_follows_ray_side() {
+        let smooth = triangle().with_normals(
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+        );
+        // Vertex normals point away from the ray; the record flips them back.
+        assert_eq!(hit_normal(&smooth, 0.2, 0.2), Vec3::new(0.0, 0.0, 1.0));
     }
 }
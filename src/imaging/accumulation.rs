@@ -0,0 +1,329 @@
+use std::ops::Range;
+
+use crate::{
+    imaging::{
+        aov::{Aov, AovStats},
+        canvas::Canvas,
+        color::luminance,
+        filter::Filter,
+    },
+    math::vec3::Color,
+};
+
+/// Running sum of the samples of one pixel, plus the mean and variance
+/// of their luminance (Welford's algorithm).
+#[derive(Debug, Clone, Default)]
+pub struct PixelStats {
+    count: u32,
+    sum: Color,
+    mean: f64,
+    m2: f64,
+}
+
+impl PixelStats {
+    pub fn add(&mut self, color: Color) {
+        self.count += 1;
+        self.sum += color;
+
+        let y = luminance(color);
+        let delta = y - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (y - self.mean);
+    }
+
+    /// Number of samples added so far.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Average color of the samples, or black if there are none.
+    pub fn color(&self) -> Color {
+        self.sum / self.count.max(1) as f64
+    }
+
+    pub fn mean_luminance(&self) -> f64 {
+        self.mean
+    }
+
+    /// Estimated standard deviation of the pixel's mean luminance.
+    pub fn standard_error(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
+        }
+        let n = self.count as f64;
+        (self.m2 / (n - 1.0) / n).sqrt()
+    }
+}
+
+/// Filter-weighted sum of the samples splatted onto one pixel.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FilmPixel {
+    pub weighted_sum: Color,
+    /// The part of `weighted_sum` that is direct light, for the lighting
+    /// AOVs.
+    pub weighted_direct: Color,
+    pub weight: f64,
+}
+
+impl FilmPixel {
+    /// The filtered color, or black where no weight has landed.
+    pub fn color(&self) -> Color {
+        self.resolve(self.weighted_sum)
+    }
+
+    /// The filtered direct light.
+    pub fn direct(&self) -> Color {
+        self.resolve(self.weighted_direct)
+    }
+
+    /// The filtered indirect light. Together with [`FilmPixel::direct`] it
+    /// makes up [`FilmPixel::color`].
+    pub fn indirect(&self) -> Color {
+        self.resolve(self.weighted_sum - self.weighted_direct)
+    }
+
+    /// Negative filter lobes can push a channel below zero, so it is
+    /// clamped.
+    fn resolve(&self, weighted: Color) -> Color {
+        if self.weight <= 0.0 {
+            return Color::default();
+        }
+        let c = weighted / self.weight;
+        Color::new(c.x.max(0.0), c.y.max(0.0), c.z.max(0.0))
+    }
+}
+
+/// Samples splatted by one group of rows, covering those rows plus the
+/// neighbours the filter reaches. Bands are filled independently and then
+/// merged into the buffer in a fixed order, so the result does not depend
+/// on how rows were spread across threads.
+#[derive(Debug, Clone)]
+pub struct FilmBand {
+    first_row: usize,
+    width: usize,
+    pixels: Vec<FilmPixel>,
+}
+
+impl FilmBand {
+    /// Adds a sample at raster position (`x`, `y`), where pixel centers lie
+    /// on integer coordinates, to every pixel of the band within the
+    /// filter's radius. `direct` is the part of `color` that is direct light.
+    pub fn splat(&mut self, filter: &Filter, (x, y): (f64, f64), color: Color, direct: Color) {
+        let rows = self.pixels.len() / self.width;
+        // Pixels p with p - radius <= x < p + radius.
+        let span = |center: f64, first: usize, len: usize| {
+            let lo = ((center - filter.radius).floor() + 1.0).max(first as f64) as usize;
+            let hi = ((center + filter.radius).floor() + 1.0).min((first + len) as f64);
+            lo..(hi.max(0.0) as usize)
+        };
+
+        for py in span(y, self.first_row, rows) {
+            for px in span(x, 0, self.width) {
+                let weight = filter.weight(x - px as f64, y - py as f64);
+                if weight != 0.0 {
+                    let pixel = &mut self.pixels[(py - self.first_row) * self.width + px];
+                    pixel.weighted_sum += color * weight;
+                    pixel.weighted_direct += direct * weight;
+                    pixel.weight += weight;
+                }
+            }
+        }
+    }
+}
+
+/// Samples gathered so far for every pixel of an image. Successive render
+/// passes add to it, and it can be resolved into a [`Canvas`] at any time.
+///
+/// Per-pixel statistics drive adaptive sampling and the surface AOVs,
+/// while the image and its direct/indirect split are resolved from the
+/// filter-weighted film.
+#[derive(Debug, Clone)]
+pub struct AccumulationBuffer {
+    pub width: u32,
+    pub height: u32,
+    pixels: Vec<PixelStats>,
+    film: Vec<FilmPixel>,
+    /// Empty unless AOVs are being gathered.
+    aovs: Vec<AovStats>,
+}
+
+impl AccumulationBuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![PixelStats::default(); (width * height) as usize],
+            film: vec![FilmPixel::default(); (width * height) as usize],
+            aovs: Vec::new(),
+        }
+    }
+
+    /// Also gathers the data behind every [`Aov`].
+    pub fn with_aovs(mut self) -> Self {
+        self.aovs = vec![AovStats::default(); self.pixels.len()];
+        self
+    }
+
+    pub fn has_aovs(&self) -> bool {
+        !self.aovs.is_empty()
+    }
+
+    pub fn pixels(&self) -> &[PixelStats] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [PixelStats] {
+        &mut self.pixels
+    }
+
+    /// The pixels together with their AOV data, which is empty unless
+    /// enabled with [`AccumulationBuffer::with_aovs`].
+    pub fn pixels_and_aovs_mut(&mut self) -> (&mut [PixelStats], &mut [AovStats]) {
+        (&mut self.pixels, &mut self.aovs)
+    }
+
+    /// An empty band for splatting the samples of `rows`.
+    pub fn film_band(&self, rows: Range<usize>, filter: &Filter) -> FilmBand {
+        let reach = filter.pixel_reach();
+        let first_row = rows.start.saturating_sub(reach);
+        let end = (rows.end + reach).min(self.height as usize);
+        let width = self.width as usize;
+        FilmBand {
+            first_row,
+            width,
+            pixels: vec![FilmPixel::default(); (end - first_row) * width],
+        }
+    }
+
+    pub fn merge_band(&mut self, band: &FilmBand) {
+        let start = band.first_row * band.width;
+        for (pixel, splat) in self.film[start..].iter_mut().zip(&band.pixels) {
+            pixel.weighted_sum += splat.weighted_sum;
+            pixel.weighted_direct += splat.weighted_direct;
+            pixel.weight += splat.weight;
+        }
+    }
+
+    /// The filtered image.
+    pub fn to_canvas(&self) -> Canvas {
+        let pixels = self.film.iter().map(FilmPixel::color).collect();
+        Canvas::from_pixels(self.width, self.height, pixels)
+    }
+
+    /// Resolves one AOV, or `None` if AOVs are not being gathered.
+    pub fn aov_canvas(&self, aov: Aov) -> Option<Canvas> {
+        if !self.has_aovs() {
+            return None;
+        }
+        let pixels = match aov {
+            Aov::Direct => self.film.iter().map(FilmPixel::direct).collect(),
+            Aov::Indirect => self.film.iter().map(FilmPixel::indirect).collect(),
+            _ => self.aovs.iter().map(|stats| stats.value(aov)).collect(),
+        };
+        Some(Canvas::from_pixels(self.width, self.height, pixels))
+    }
+
+    pub fn sample_counts(&self) -> Vec<u32> {
+        self.pixels.iter().map(PixelStats::count).collect()
+    }
+
+    pub fn total_samples(&self) -> u64 {
+        self.pixels.iter().map(|p| p.count as u64).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::imaging::filter::FilterType;
+
+    #[test]
+    fn test_pixel_stats() {
+        let mut stats = PixelStats::default();
+        for y in [1.0, 2.0, 3.0, 4.0] {
+            stats.add(Color::new(y, y, y));
+        }
+        assert_eq!(stats.color(), Color::new(2.5, 2.5, 2.5));
+        // Sample variance 5/3, over 4 samples.
+        assert!((stats.standard_error() - (5.0f64 / 12.0).sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_empty_pixels_resolve_to_black() {
+        let filter = Filter::default();
+        let mut buffer = AccumulationBuffer::new(2, 1);
+        let mut band = buffer.film_band(0..1, &filter);
+        band.splat(
+            &filter,
+            (1.2, 0.0),
+            Color::new(1.0, 0.5, 0.25),
+            Color::default(),
+        );
+        buffer.merge_band(&band);
+
+        let canvas = buffer.to_canvas();
+        assert_eq!(canvas.pixels()[0], Color::default());
+        assert_eq!(canvas.pixels()[1], Color::new(1.0, 0.5, 0.25));
+    }
+
+    #[test]
+    fn test_splats_reach_neighbouring_rows() {
+        let filter = Filter::new(FilterType::Tent);
+        let mut buffer = AccumulationBuffer::new(3, 4);
+        // Rows 1 and 2, widened by one row on each side.
+        let mut band = buffer.film_band(1..3, &filter);
+        band.splat(
+            &filter,
+            (1.0, 1.5),
+            Color::new(1.0, 1.0, 1.0),
+            Color::default(),
+        );
+        band.splat(
+            &filter,
+            (1.0, 2.5),
+            Color::new(0.0, 0.0, 0.0),
+            Color::default(),
+        );
+        buffer.merge_band(&band);
+
+        let canvas = buffer.to_canvas();
+        let at = |x: usize, y: usize| canvas.pixels()[y * 3 + x].x;
+        assert_eq!(at(1, 0), 0.0);
+        assert_eq!(at(1, 1), 1.0);
+        assert_eq!(at(1, 2), 0.5);
+        assert_eq!(at(1, 3), 0.0);
+        assert_eq!(at(0, 1), 0.0);
+    }
+
+    #[test]
+    fn test_lighting_is_filtered_like_the_beauty() {
+        let filter = Filter::new(FilterType::Gaussian);
+        let mut buffer = AccumulationBuffer::new(3, 1).with_aovs();
+        let mut band = buffer.film_band(0..1, &filter);
+        band.splat(
+            &filter,
+            (0.7, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+            Color::new(0.25, 0.25, 0.25),
+        );
+        band.splat(
+            &filter,
+            (1.4, 0.0),
+            Color::new(4.0, 2.0, 0.0),
+            Color::default(),
+        );
+        buffer.merge_band(&band);
+
+        let beauty = buffer.to_canvas();
+        let direct = buffer.aov_canvas(Aov::Direct).unwrap();
+        let indirect = buffer.aov_canvas(Aov::Indirect).unwrap();
+        for i in 0..3 {
+            let sum = direct.pixels()[i] + indirect.pixels()[i];
+            assert!((sum - beauty.pixels()[i]).length() < 1e-12);
+        }
+        // The first sample's direct light reaches every pixel, mixed with
+        // the second sample wherever that lands too.
+        let direct: Vec<f64> = direct.pixels().iter().map(|c| c.x).collect();
+        assert!(direct.iter().all(|&d| d > 0.0 && d < 0.25), "{direct:?}");
+    }
+}
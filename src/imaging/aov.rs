@@ -0,0 +1,252 @@
+use std::str::FromStr;
+
+use crate::{
+    imaging::{canvas::Canvas, color::srgb_to_linear},
+    math::{
+        utils,
+        vec3::{Color, Vec3},
+    },
+};
+
+/// Arbitrary output variable: a per-pixel image written alongside the
+/// beauty render, for compositing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aov {
+    /// Distance from the camera to the first hit; 0 where nothing was hit.
+    Depth,
+    /// Shading normal at the first hit, remapped from [-1, 1] to [0, 1].
+    Normal,
+    /// Surface color at the first hit.
+    Albedo,
+    /// A distinct color per material.
+    MaterialId,
+    /// A distinct color per scene object.
+    ObjectId,
+    /// Light that reached the camera after at most one bounce.
+    Direct,
+    /// Light that took two or more bounces.
+    Indirect,
+}
+
+impl FromStr for Aov {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "depth" | "z" => Ok(Aov::Depth),
+            "normal" => Ok(Aov::Normal),
+            "albedo" => Ok(Aov::Albedo),
+            "material-id" | "material" => Ok(Aov::MaterialId),
+            "object-id" | "object" => Ok(Aov::ObjectId),
+            "direct" => Ok(Aov::Direct),
+            "indirect" => Ok(Aov::Indirect),
+            _ => Err(format!(
+                "unknown AOV '{s}' (expected 'depth', 'normal', 'albedo', 'material-id', \
+                 'object-id', 'direct' or 'indirect')"
+            )),
+        }
+    }
+}
+
+impl Aov {
+    /// Appended to the output file name, as in `image_depth.png`.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Aov::Depth => "depth",
+            Aov::Normal => "normal",
+            Aov::Albedo => "albedo",
+            Aov::MaterialId => "material_id",
+            Aov::ObjectId => "object_id",
+            Aov::Direct => "direct",
+            Aov::Indirect => "indirect",
+        }
+    }
+
+    /// Whether the image holds measurements rather than colors, so that
+    /// 8-bit files should store it without sRGB encoding.
+    pub fn is_data(self) -> bool {
+        matches!(self, Aov::Depth | Aov::Normal)
+    }
+
+    /// Adapts an image of this AOV to 8-bit formats, which sRGB-encode
+    /// and clip: depth is scaled to [0, 1], and data images are stored
+    /// linearly by undoing the encoding in advance.
+    pub fn for_8bit_output(self, canvas: &Canvas) -> Canvas {
+        let scale = match self {
+            Aov::Depth => {
+                let max = canvas.pixels().iter().map(|c| c.x).fold(0.0, f64::max);
+                if max > 0.0 { 1.0 / max } else { 1.0 }
+            }
+            _ => 1.0,
+        };
+        let decode = |c: Color| {
+            let c = c * scale;
+            if self.is_data() {
+                let f = |x: f64| srgb_to_linear(x.clamp(0.0, 1.0));
+                Color::new(f(c.x), f(c.y), f(c.z))
+            } else {
+                c
+            }
+        };
+
+        let pixels = canvas.pixels().iter().map(|&c| decode(c)).collect();
+        Canvas::from_pixels(canvas.width, canvas.height, pixels)
+    }
+
+    /// Whether the image holds radiance, to be tone mapped like the beauty
+    /// render.
+    pub fn is_lighting(self) -> bool {
+        matches!(self, Aov::Direct | Aov::Indirect)
+    }
+}
+
+/// What a camera ray found at its first hit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceSample {
+    pub depth: f64,
+    pub normal: Vec3,
+    pub albedo: Color,
+    pub object_id: u32,
+    pub material_id: u32,
+}
+
+/// Per-pixel sums behind the AOV images.
+#[derive(Debug, Clone, Default)]
+pub struct AovStats {
+    samples: u32,
+    hits: u32,
+    depth: f64,
+    normal: Vec3,
+    albedo: Color,
+    /// IDs seen by the pixel's first hit, as blending IDs is meaningless.
+    object_id: u32,
+    material_id: u32,
+}
+
+impl AovStats {
+    pub fn add(&mut self, surface: Option<&SurfaceSample>) {
+        if let Some(surface) = surface {
+            if self.hits == 0 {
+                self.object_id = surface.object_id;
+                self.material_id = surface.material_id;
+            }
+            self.hits += 1;
+            self.depth += surface.depth;
+            self.normal += surface.normal;
+            self.albedo += surface.albedo;
+        }
+
+        self.samples += 1;
+    }
+
+    /// The pixel's value of a surface AOV. Lighting AOVs are filtered along
+    /// with the beauty render and come from the film instead.
+    pub fn value(&self, aov: Aov) -> Color {
+        let per_hit = 1.0 / self.hits.max(1) as f64;
+
+        match aov {
+            Aov::Depth => {
+                let depth = self.depth * per_hit;
+                Color::new(depth, depth, depth)
+            }
+            Aov::Normal if self.hits == 0 => Color::default(),
+            Aov::Normal => {
+                let n = self.normal.unit_vector();
+                (n + Vec3::new(1.0, 1.0, 1.0)) * 0.5
+            }
+            Aov::Albedo => self.albedo * per_hit,
+            Aov::MaterialId => id_color(self.material_id),
+            Aov::ObjectId => id_color(self.object_id),
+            Aov::Direct | Aov::Indirect => {
+                unreachable!("{aov:?} is resolved from the film, not AovStats")
+            }
+        }
+    }
+}
+
+/// A reproducible, well-spread color for an ID; black for 0 (no ID).
+pub fn id_color(id: u32) -> Color {
+    if id == 0 {
+        return Color::default();
+    }
+    let hash = utils::stream_seed(id as u64, &[]);
+    let channel = |shift: u32| 0.15 + 0.85 * ((hash >> shift) & 0xff) as f64 / 255.0;
+    Color::new(channel(0), channel(8), channel(16))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn surface(depth: f64, normal: Vec3, object_id: u32) -> SurfaceSample {
+        SurfaceSample {
+            depth,
+            normal,
+            albedo: Color::new(0.5, 0.25, 1.0),
+            object_id,
+            material_id: 1,
+        }
+    }
+
+    #[test]
+    fn test_values_average_over_hits() {
+        let mut stats = AovStats::default();
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        stats.add(Some(&surface(2.0, up, 4)));
+        stats.add(Some(&surface(4.0, up, 5)));
+        stats.add(None);
+
+        assert_eq!(stats.value(Aov::Depth), Color::new(3.0, 3.0, 3.0));
+        assert_eq!(stats.value(Aov::Normal), Color::new(0.5, 1.0, 0.5));
+        assert_eq!(stats.value(Aov::Albedo), Color::new(0.5, 0.25, 1.0));
+        assert_eq!(stats.value(Aov::ObjectId), id_color(4));
+    }
+
+    #[test]
+    fn test_misses_are_black() {
+        let mut stats = AovStats::default();
+        stats.add(None);
+        for aov in [Aov::Depth, Aov::Normal, Aov::Albedo, Aov::ObjectId] {
+            assert_eq!(stats.value(aov), Color::default(), "{aov:?}");
+        }
+    }
+
+    #[test]
+    fn test_ids_come_from_the_first_hit() {
+        let mut stats = AovStats::default();
+        stats.add(None);
+        stats.add(Some(&surface(1.0, Vec3::new(0.0, 1.0, 0.0), 3)));
+        stats.add(Some(&surface(1.0, Vec3::new(0.0, 1.0, 0.0), 6)));
+
+        assert_eq!(stats.value(Aov::ObjectId), id_color(3));
+        assert_eq!(stats.value(Aov::MaterialId), id_color(1));
+    }
+
+    #[test]
+    fn test_id_colors_differ() {
+        assert_eq!(id_color(0), Color::default());
+        assert_ne!(id_color(1), id_color(2));
+        assert_eq!(id_color(7), id_color(7));
+    }
+
+    #[test]
+    fn test_parse_aov() {
+        assert_eq!("Material-ID".parse(), Ok(Aov::MaterialId));
+        assert_eq!("z".parse(), Ok(Aov::Depth));
+        assert!("motion".parse::<Aov>().is_err());
+    }
+
+    #[test]
+    fn test_8bit_depth_is_normalized_and_linear() {
+        let canvas = Canvas::from_pixels(
+            2,
+            1,
+            vec![Color::new(2.0, 2.0, 2.0), Color::new(8.0, 8.0, 8.0)],
+        );
+        let display = Aov::Depth.for_8bit_output(&canvas);
+        let tonemap = crate::imaging::tonemap::ToneMap::default();
+        let bytes = |c: Color| crate::imaging::color::to_rgb_bytes(tonemap.apply(c));
+        assert_eq!(bytes(display.pixels()[0]), [64, 64, 64]);
+        assert_eq!(bytes(display.pixels()[1]), [255, 255, 255]);
+    }
+}
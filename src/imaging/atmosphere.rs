@@ -0,0 +1,264 @@
+use crate::{
+    geometry::hittable::HitRecord,
+    imaging::material::MaterialKind,
+    math::{aabb::Aabb, interval::Interval, ray::Ray},
+};
+
+/// How the density of an [`Atmosphere`] varies through the scene.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DensityProfile {
+    /// The same density everywhere.
+    Homogeneous,
+    /// Density falling off as `exp(-falloff * (y - base_height))`, so fog
+    /// gathers near the ground and thins out with height.
+    Exponential { base_height: f64, falloff: f64 },
+}
+
+/// A participating medium filling the whole scene, for haze and
+/// atmospheric perspective.
+///
+/// Every ray, including those that escape to the background, may be
+/// scattered on its way by the `phase` material. Distances are sampled
+/// analytically, so the fog costs one random number per ray.
+///
+/// The fog fills `bounds`, so that rays leaving the box still see the
+/// background through homogeneous fog.
+#[derive(Debug, Clone)]
+pub struct Atmosphere {
+    /// Density at `base_height`, or everywhere for homogeneous fog.
+    density: f64,
+    profile: DensityProfile,
+    phase: MaterialKind,
+    bounds: Aabb,
+}
+
+impl Atmosphere {
+    pub fn new(density: f64, profile: DensityProfile, phase: MaterialKind) -> Self {
+        Self {
+            density,
+            profile,
+            phase,
+            bounds: Aabb::UNIVERSE,
+        }
+    }
+
+    /// Confines the fog to the world-space box `bounds`.
+    pub fn with_bounds(mut self, bounds: Aabb) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    /// The part of `ray` before `t_max` that runs through the fog, as the
+    /// same ray restarted where it enters, and the parameter range inside.
+    fn clip(&self, ray: &Ray, t_max: f64) -> Option<(Ray, Interval)> {
+        let inside = self.bounds.clip(ray, Interval::new(0.0, t_max))?;
+        Some((Ray::new(ray.at(inside.min), ray.direction), inside))
+    }
+
+    /// Density along `ray` at its origin, and its exponential rate of change
+    /// per unit of the ray parameter.
+    fn density_along(&self, ray: &Ray) -> (f64, f64) {
+        let speed = ray.direction.length();
+        match self.profile {
+            DensityProfile::Homogeneous => (self.density * speed, 0.0),
+            DensityProfile::Exponential {
+                base_height,
+                falloff,
+            } => (
+                self.density * speed * (-falloff * (ray.origin.y - base_height)).exp(),
+                falloff * ray.direction.y,
+            ),
+        }
+    }
+
+    /// Optical depth between the origin of `ray` and `ray.at(t)`.
+    fn optical_depth(&self, ray: &Ray, t: f64) -> f64 {
+        let (start, rate) = self.density_along(ray);
+        if rate.abs() < 1e-9 {
+            return start * t;
+        }
+        // The integral of start · exp(-rate · s) from 0 to t.
+        -start * (-rate * t).exp_m1() / rate
+    }
+
+    /// Fraction of light that crosses from the origin of `ray` to
+    /// `ray.at(t)` without being scattered.
+    pub fn transmittance(&self, ray: &Ray, t: f64) -> f64 {
+        let Some((entry, inside)) = self.clip(ray, t) else {
+            return 1.0;
+        };
+        let length = inside.size();
+        if length == f64::INFINITY {
+            let (start, rate) = self.density_along(&entry);
+            // Only rays climbing out of height fog see a finite depth.
+            return if start <= 0.0 {
+                1.0
+            } else if rate > 1e-9 && start.is_finite() {
+                (-start / rate).exp()
+            } else {
+                0.0
+            };
+        }
+        (-self.optical_depth(&entry, length)).exp()
+    }
+
+    /// Samples where `ray` is scattered before `t_max` from the uniform
+    /// variate `u`, or `None` if it gets there unscattered.
+    pub fn sample_distance(&self, ray: &Ray, t_max: f64, u: f64) -> Option<f64> {
+        let (entry, inside) = self.clip(ray, t_max)?;
+        // Invert the transmittance: scatter where it drops to 1 - u.
+        let depth = -(1.0 - u).ln();
+        let (start, rate) = self.density_along(&entry);
+        if start <= 0.0 {
+            return None;
+        }
+
+        let t = if rate.abs() < 1e-9 {
+            depth / start
+        } else {
+            let x = -depth * rate / start;
+            if x <= -1.0 {
+                // More depth than the whole way up holds.
+                return None;
+            }
+            -x.ln_1p() / rate
+        };
+        let t = inside.min + t;
+        (t < inside.max).then_some(t)
+    }
+
+    /// The scattering event at `ray.at(t)`.
+    pub fn scatter_record(&self, ray: &Ray, t: f64) -> HitRecord<'_> {
+        // There is no surface, so the normal only has to face the ray.
+        let normal = -ray.direction.unit_vector();
+        HitRecord::new(ray.at(t), normal, t, (0.0, 0.0), ray, &self.phase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        imaging::material::Isotropic,
+        math::vec3::{Color, Point3, Vec3},
+    };
+
+    fn fog(profile: DensityProfile) -> Atmosphere {
+        Atmosphere::new(
+            0.4,
+            profile,
+            MaterialKind::Isotropic(Isotropic::new(Color::new(1.0, 1.0, 1.0))),
+        )
+    }
+
+    fn height_fog() -> Atmosphere {
+        fog(DensityProfile::Exponential {
+            base_height: 1.0,
+            falloff: 0.7,
+        })
+    }
+
+    #[test]
+    fn test_homogeneous_transmittance() {
+        let fog = fog(DensityProfile::Homogeneous);
+        let ray = Ray::new(Point3::new(0.0, 3.0, 0.0), Vec3::new(0.0, 0.0, -2.0));
+        // Five units of travel at density 0.4.
+        assert!((fog.transmittance(&ray, 2.5) - (-2.0f64).exp()).abs() < 1e-12);
+        assert_eq!(fog.transmittance(&ray, f64::INFINITY), 0.0);
+    }
+
+    #[test]
+    fn test_fog_fills_its_bounds() {
+        let bounds = Aabb::from_points(Point3::new(-1.0, 0.0, -4.0), Point3::new(1.0, 5.0, 1.0));
+        let fog = fog(DensityProfile::Homogeneous).with_bounds(bounds);
+        let ray = Ray::new(Point3::new(0.0, 3.0, 0.0), Vec3::new(0.0, 0.0, -2.0));
+        // Escaping rays only cross the four units of fog left in the box.
+        let escape = (-1.6f64).exp();
+        assert!((fog.transmittance(&ray, f64::INFINITY) - escape).abs() < 1e-12);
+        assert!((fog.transmittance(&ray, 10.0) - escape).abs() < 1e-12);
+        assert!((fog.transmittance(&ray, 1.25) - (-1.0f64).exp()).abs() < 1e-12);
+
+        // Scattering only happens inside the box.
+        for i in 0..100 {
+            let u = (i as f64 + 0.5) / 100.0;
+            match fog.sample_distance(&ray, f64::INFINITY, u) {
+                Some(t) => assert!(t < 2.0),
+                None => assert!(1.0 - u <= escape + 1e-12),
+            }
+        }
+    }
+
+    #[test]
+    fn test_rays_through_the_same_region_see_the_same_depth() {
+        let bounds = Aabb::from_points(Point3::new(-1.0, 0.0, -1.0), Point3::new(1.0, 2.0, 1.0));
+        for fog in [fog(DensityProfile::Homogeneous), height_fog()] {
+            let fog = fog.with_bounds(bounds);
+            // Both cross the box along the same line, from different
+            // distances and at different speeds.
+            let near = Ray::new(Point3::new(-2.0, 0.5, 0.0), Vec3::new(1.0, 0.25, 0.0));
+            let far = Ray::new(Point3::new(-10.0, -1.5, 0.0), Vec3::new(4.0, 1.0, 0.0));
+            let (a, b) = (
+                fog.transmittance(&near, f64::INFINITY),
+                fog.transmittance(&far, f64::INFINITY),
+            );
+            assert!(a < 1.0 && (a - b).abs() < 1e-12, "{a} vs {b}");
+
+            // Scattering points agree too.
+            for i in 0..10 {
+                let u = (i as f64 + 0.5) / 10.0;
+                let at = |ray: &Ray| {
+                    fog.sample_distance(ray, f64::INFINITY, u)
+                        .map(|t| ray.at(t))
+                };
+                match (at(&near), at(&far)) {
+                    (Some(p), Some(q)) => assert!((p - q).length() < 1e-9),
+                    (p, q) => assert_eq!(p.is_some(), q.is_some()),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_height_fog_depth_matches_numeric_integral() {
+        let fog = height_fog();
+        let ray = Ray::new(Point3::new(0.2, -0.5, 0.0), Vec3::new(1.0, 0.6, -0.3));
+        let t = 4.0;
+
+        let n = 100_000;
+        let dt = t / n as f64;
+        let numeric: f64 = (0..n)
+            .map(|i| {
+                let y = ray.at((i as f64 + 0.5) * dt).y;
+                0.4 * (-0.7 * (y - 1.0)).exp() * ray.direction.length() * dt
+            })
+            .sum();
+        assert!((fog.optical_depth(&ray, t) - numeric).abs() < 1e-6);
+
+        // Rising rays keep some light all the way up; sinking ones do not.
+        assert!(fog.transmittance(&ray, f64::INFINITY) > 0.0);
+        let down = Ray::new(ray.origin, Vec3::new(1.0, -0.1, 0.0));
+        assert_eq!(fog.transmittance(&down, f64::INFINITY), 0.0);
+    }
+
+    #[test]
+    fn test_sampled_distances_invert_transmittance() {
+        let fog = height_fog();
+        for direction in [
+            Vec3::new(1.0, 0.5, 0.0),
+            Vec3::new(0.3, -0.8, 0.2),
+            Vec3::new(0.0, 0.0, 1.0),
+        ] {
+            let ray = Ray::new(Point3::new(0.0, 1.5, 0.0), direction);
+            let escape = fog.transmittance(&ray, f64::INFINITY);
+            for i in 0..100 {
+                let u = (i as f64 + 0.5) / 100.0;
+                match fog.sample_distance(&ray, f64::INFINITY, u) {
+                    Some(t) => {
+                        assert!((fog.transmittance(&ray, t) - (1.0 - u)).abs() < 1e-9);
+                    }
+                    None => assert!(1.0 - u <= escape + 1e-12),
+                }
+            }
+        }
+    }
+}
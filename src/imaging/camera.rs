@@ -1,4 +1,7 @@
-use crate::math::{ray::Ray, vec3::Vec3};
+use crate::{
+    imaging::sampler::{Sampler, square_to_disk},
+    math::{aabb::Aabb, ray::Ray, vec3::Vec3},
+};
 
 #[derive(Debug, Clone)]
 pub struct Camera {
@@ -16,23 +19,29 @@ impl Camera {
         CameraBuilder::default()
     }
 
+    /// Where camera rays start, before defocus blur moves them on the lens.
+    pub fn center(&self) -> Vec3 {
+        self.center
+    }
+
     /// Generates a ray for a given normalized coordinate (s, t).
-    /// s and t should be in the range [0.0, 1.0].
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
+    /// s and t should be in the range [0.0, 1.0]. The lens position, if any,
+    /// is drawn from `sampler`.
+    pub fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
         let pixel_center = self.pixel00_loc + (self.pixel_delta_u * s) + (self.pixel_delta_v * t);
 
         let ray_origin = if self.defocus_angle <= 0.0 {
             self.center
         } else {
-            self.defocus_disk_sample()
+            self.defocus_disk_sample(sampler)
         };
 
         let ray_direction = pixel_center - ray_origin;
         Ray::new(ray_origin, ray_direction)
     }
 
-    fn defocus_disk_sample(&self) -> Vec3 {
-        let p = Vec3::random_in_unit_disk();
+    fn defocus_disk_sample(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        let p = square_to_disk(sampler.next_2d());
         self.center + (self.defocus_disk_u * p.x) + (self.defocus_disk_v * p.y)
     }
 }
@@ -98,6 +107,29 @@ impl CameraBuilder {
         self
     }
 
+    /// Aims the camera at the center of `bbox` and moves it back along the
+    /// current viewing direction until the whole box fits in view.
+    /// Call after setting the aspect ratio and field of view.
+    pub fn frame(mut self, bbox: &Aabb) -> Self {
+        if bbox.is_empty() {
+            return self;
+        }
+
+        let center = bbox.centroid();
+        let radius = 0.5 * bbox.size().length();
+
+        let vertical = self.vertical_fov.to_radians();
+        let horizontal = 2.0 * ((vertical / 2.0).tan() * self.aspect_ratio).atan();
+        let half_angle = 0.5 * vertical.min(horizontal);
+        let distance = radius / half_angle.sin();
+
+        let direction = (self.lookfrom - self.lookat).unit_vector();
+        self.lookat = center;
+        self.lookfrom = center + direction * distance;
+        self.focus_dist = distance;
+        self
+    }
+
     pub fn build(self) -> Camera {
         let theta = self.vertical_fov.to_radians();
         let h = (theta / 2.0).tan();
@@ -130,3 +162,51 @@ impl CameraBuilder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Where the ray from the camera center through `p` crosses the
+    /// viewport, as (s, t) in the coordinates `get_ray` takes.
+    fn project(camera: &Camera, p: Vec3) -> (f64, f64) {
+        let normal = camera.pixel_delta_u.cross(camera.pixel_delta_v);
+        let direction = p - camera.center;
+        let t = (camera.pixel00_loc - camera.center).dot(normal) / direction.dot(normal);
+        let offset = camera.center + direction * t - camera.pixel00_loc;
+        (
+            offset.dot(camera.pixel_delta_u) / camera.pixel_delta_u.length_squared(),
+            offset.dot(camera.pixel_delta_v) / camera.pixel_delta_v.length_squared(),
+        )
+    }
+
+    #[test]
+    fn test_frame_fits_the_box() {
+        let bbox = Aabb::from_points(Vec3::new(-3.0, 0.0, -1.0), Vec3::new(5.0, 2.0, 4.0));
+        let camera = Camera::builder()
+            .aspect_ratio(2.0)
+            .fov(30.0)
+            .look_from(Vec3::new(1.0, 1.0, 1.0))
+            .look_at(Vec3::new(0.0, 0.0, 0.0))
+            .frame(&bbox)
+            .build();
+
+        let (s, t) = project(&camera, bbox.centroid());
+        assert!((s - 0.5).abs() < 1e-9 && (t - 0.5).abs() < 1e-9, "{s}, {t}");
+
+        let pick = |axis: usize, upper: bool| {
+            let interval = bbox.axis_interval(axis);
+            if upper { interval.max } else { interval.min }
+        };
+        for i in 0..8 {
+            let corner = Vec3::new(
+                pick(0, i & 1 != 0),
+                pick(1, i & 2 != 0),
+                pick(2, i & 4 != 0),
+            );
+            let (s, t) = project(&camera, corner);
+            assert!((0.0..=1.0).contains(&s), "corner {i}: s = {s}");
+            assert!((0.0..=1.0).contains(&t), "corner {i}: t = {t}");
+        }
+    }
+}
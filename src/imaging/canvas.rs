@@ -1,8 +1,15 @@
-use image::{Rgb, RgbImage};
+use exr::prelude::{SpecificChannels, Vec2, WritableImage};
+use image::{
+    ImageFormat, Rgb, Rgb32FImage, RgbImage,
+    error::{EncodingError, ImageFormatHint},
+};
 use std::io::{self, Write};
 use std::path::Path;
 
-use crate::{imaging::color::to_rgb_bytes, math::vec3::Color};
+use crate::{
+    imaging::{color::to_rgb_bytes, tonemap::ToneMap},
+    math::vec3::Color,
+};
 
 /// Represents a canvas of pixels.
 pub struct Canvas {
@@ -25,18 +32,62 @@ impl Canvas {
         }
     }
 
+    /// Wraps `width * height` pixels given in row-major order.
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+        assert_eq!(pixels.len(), (width * height) as usize);
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Visualizes per-pixel sample counts on a black, blue, red, yellow,
+    /// white ramp, with white at `max` samples.
+    pub fn heatmap(width: u32, height: u32, counts: &[u32], max: u32) -> Self {
+        const RAMP: [Color; 5] = [
+            Color::new(0.0, 0.0, 0.0),
+            Color::new(0.0, 0.0, 1.0),
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(1.0, 1.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ];
+
+        let pixels = counts
+            .iter()
+            .map(|&count| {
+                let t = (count as f64 / max.max(1) as f64).clamp(0.0, 1.0);
+                let x = t * (RAMP.len() - 1) as f64;
+                let i = (x as usize).min(RAMP.len() - 2);
+                let f = x - i as f64;
+                RAMP[i] * (1.0 - f) + RAMP[i + 1] * f
+            })
+            .collect();
+
+        Self::from_pixels(width, height, pixels)
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
     pub fn pixels_mut(&mut self) -> &mut [Color] {
         &mut self.pixels
     }
 
-    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), image::ImageError> {
+    /// Tone maps and sRGB-encodes the image into an 8-bit PNG.
+    pub fn save_png<P: AsRef<Path>>(
+        &self,
+        path: P,
+        tonemap: &ToneMap,
+    ) -> Result<(), image::ImageError> {
         let mut img = RgbImage::new(self.width, self.height);
 
         for (x, y, pixel) in img.enumerate_pixels_mut() {
             let idx = (y * self.width + x) as usize;
             let linear_color = self.pixels[idx];
 
-            let rgb = to_rgb_bytes(linear_color);
+            let rgb = to_rgb_bytes(tonemap.apply(linear_color));
 
             *pixel = Rgb(rgb);
         }
@@ -44,13 +95,106 @@ impl Canvas {
         img.save(path)
     }
 
-    pub fn write_ppm<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+    /// Writes unclamped linear colors as 32-bit float OpenEXR. Blocks are
+    /// compressed on one thread so the file bytes are reproducible.
+    pub fn save_exr<P: AsRef<Path>>(&self, path: P) -> Result<(), image::ImageError> {
+        let size = (self.width as usize, self.height as usize);
+        let channels = SpecificChannels::rgb(|Vec2(x, y): Vec2<usize>| {
+            let color = self.pixels[y * size.0 + x];
+            (color.x as f32, color.y as f32, color.z as f32)
+        });
+
+        exr::prelude::Image::from_channels(size, channels)
+            .write()
+            .non_parallel()
+            .to_file(path)
+            .map_err(|err| {
+                image::ImageError::Encoding(EncodingError::new(
+                    ImageFormatHint::Exact(ImageFormat::OpenExr),
+                    err,
+                ))
+            })
+    }
+
+    /// Writes unclamped linear colors as Radiance HDR (RGBE).
+    pub fn save_hdr<P: AsRef<Path>>(&self, path: P) -> Result<(), image::ImageError> {
+        self.to_rgb32f().save_with_format(path, ImageFormat::Hdr)
+    }
+
+    fn to_rgb32f(&self) -> Rgb32FImage {
+        Rgb32FImage::from_fn(self.width, self.height, |x, y| {
+            let color = self.pixels[(y * self.width + x) as usize];
+            Rgb([color.x as f32, color.y as f32, color.z as f32])
+        })
+    }
+
+    /// Tone maps and sRGB-encodes the image as a plain-text PPM.
+    pub fn write_ppm<W: Write>(&self, writer: &mut W, tonemap: &ToneMap) -> io::Result<()> {
         writeln!(writer, "P3\n{} {}\n255", self.width, self.height)?;
 
         for pixel in &self.pixels {
-            crate::imaging::color::write_color(*pixel, writer);
+            crate::imaging::color::write_color(tonemap.apply(*pixel), writer);
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canvas_with_highlight() -> Canvas {
+        let mut canvas = Canvas::new(4, 2.0);
+        canvas.pixels_mut()[5] = Color::new(12.5, 0.25, 3.0);
+        canvas
+    }
+
+    fn assert_round_trip(extension: &str, save: fn(&Canvas, &Path) -> image::ImageResult<()>) {
+        let path =
+            std::env::temp_dir().join(format!("rust-rt-canvas-{}.{extension}", std::process::id()));
+        save(&canvas_with_highlight(), &path).unwrap();
+        let image = image::open(&path).unwrap().into_rgb32f();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(image.dimensions(), (4, 2));
+        let Rgb([r, g, b]) = *image.get_pixel(1, 1);
+        // RGBE shares one exponent, so HDR keeps about 1% precision.
+        assert!((r - 12.5).abs() < 0.1, "red = {r}");
+        assert!((g - 0.25).abs() < 0.1, "green = {g}");
+        assert!((b - 3.0).abs() < 0.1, "blue = {b}");
+        assert_eq!(*image.get_pixel(0, 0), Rgb([0.0, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn test_exr_keeps_highlights() {
+        assert_round_trip("exr", |canvas, path| canvas.save_exr(path));
+    }
+
+    #[test]
+    fn test_hdr_keeps_highlights() {
+        assert_round_trip("hdr", |canvas, path| canvas.save_hdr(path));
+    }
+
+    #[test]
+    fn test_heatmap_ramp() {
+        let heatmap = Canvas::heatmap(5, 1, &[0, 16, 32, 64, 100], 64);
+        assert_eq!(
+            heatmap.pixels(),
+            [
+                Color::new(0.0, 0.0, 0.0),
+                Color::new(0.0, 0.0, 1.0),
+                Color::new(1.0, 0.0, 0.0),
+                Color::new(1.0, 1.0, 1.0),
+                Color::new(1.0, 1.0, 1.0),
+            ]
+        );
+
+        // With no maximum, any sample at all is white.
+        let heatmap = Canvas::heatmap(2, 1, &[0, 3], 0);
+        assert_eq!(
+            heatmap.pixels(),
+            [Color::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0)]
+        );
+    }
+}
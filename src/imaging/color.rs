@@ -1,17 +1,11 @@
 use crate::math::vec3::Color;
 use std::io::Write;
 
-/// Converts a linear color to gamma-corrected RGB bytes.
+/// Converts a display-linear color to sRGB-encoded RGB bytes, clipping
+/// anything outside [0, 1].
 pub fn to_rgb_bytes(color: Color) -> [u8; 3] {
-    let r = linear_to_gamma(color.x);
-    let g = linear_to_gamma(color.y);
-    let b = linear_to_gamma(color.z);
-
-    let ir = (255.999 * r.clamp(0.0, 0.999)) as u8;
-    let ig = (255.999 * g.clamp(0.0, 0.999)) as u8;
-    let ib = (255.999 * b.clamp(0.0, 0.999)) as u8;
-
-    [ir, ig, ib]
+    let encode = |c: f64| (255.0 * linear_to_srgb(c.clamp(0.0, 1.0))).round() as u8;
+    [encode(color.x), encode(color.y), encode(color.z)]
 }
 
 /// Writes a color to a writer in PPM format (space-separated RGB values).
@@ -20,9 +14,48 @@ pub fn write_color<T: Write>(color: Color, handle: &mut T) {
     writeln!(handle, "{ir} {ig} {ib}").unwrap();
 }
 
-pub fn linear_to_gamma(linear: f64) -> f64 {
-    if linear > 0.0 {
-        return linear.sqrt();
+/// Relative luminance of a linear Rec. 709 / sRGB color.
+pub fn luminance(color: Color) -> f64 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+/// The sRGB transfer function: linear light to encoded values.
+pub fn linear_to_srgb(linear: f64) -> f64 {
+    if linear <= 0.0031308 {
+        12.92 * linear.max(0.0)
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Inverse of [`linear_to_srgb`].
+pub fn srgb_to_linear(encoded: f64) -> f64 {
+    if encoded <= 0.04045 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_srgb_round_trip() {
+        for i in 0..=255 {
+            let encoded = i as f64 / 255.0;
+            assert!((linear_to_srgb(srgb_to_linear(encoded)) - encoded).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_srgb_reference_values() {
+        assert_eq!(to_rgb_bytes(Color::new(0.0, 1.0, 2.0)), [0, 255, 255]);
+        // Linear 0.216 encodes to 128, not the 0.25 a plain 2.0 gamma gives.
+        assert_eq!(
+            to_rgb_bytes(Color::new(0.216, 0.216, 0.216)),
+            [128, 128, 128]
+        );
     }
-    return 0.0;
 }
@@ -0,0 +1,193 @@
+use std::{f64::consts::PI, str::FromStr};
+
+use serde::Deserialize;
+
+/// Shape of the reconstruction filter that spreads each sample over the
+/// pixels around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum FilterType {
+    /// Equal weight within the radius; at 0.5 every sample only counts
+    /// towards its own pixel.
+    #[default]
+    Box,
+    /// Weight falling linearly to zero at the radius.
+    Tent,
+    /// Gaussian bell, shifted so it reaches zero at the radius.
+    Gaussian,
+    /// Mitchell-Netravali cubic (B = C = 1/3): sharp, with slight ringing.
+    Mitchell,
+    /// Three-lobe Lanczos windowed sinc: sharpest, with the most ringing.
+    Lanczos,
+}
+
+impl FromStr for FilterType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "box" => Ok(FilterType::Box),
+            "tent" | "triangle" => Ok(FilterType::Tent),
+            "gaussian" => Ok(FilterType::Gaussian),
+            "mitchell" => Ok(FilterType::Mitchell),
+            "lanczos" => Ok(FilterType::Lanczos),
+            _ => Err(format!(
+                "unknown filter '{s}' (expected 'box', 'tent', 'gaussian', 'mitchell' or 'lanczos')"
+            )),
+        }
+    }
+}
+
+impl FilterType {
+    /// Radius, in pixels, used when none is given.
+    pub fn default_radius(self) -> f64 {
+        match self {
+            FilterType::Box => 0.5,
+            FilterType::Tent => 1.0,
+            FilterType::Gaussian => 1.5,
+            FilterType::Mitchell | FilterType::Lanczos => 2.0,
+        }
+    }
+}
+
+/// Separable pixel reconstruction filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Filter {
+    pub kind: FilterType,
+    /// Half-width of the filter's support, in pixels.
+    pub radius: f64,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::new(FilterType::default())
+    }
+}
+
+impl Filter {
+    pub fn new(kind: FilterType) -> Self {
+        Self {
+            kind,
+            radius: kind.default_radius(),
+        }
+    }
+
+    /// Smallest radius that still lets every sample count towards the
+    /// pixel it was taken in.
+    pub const MIN_RADIUS: f64 = 0.5;
+
+    /// How many pixels beyond its own a sample can reach.
+    pub fn pixel_reach(&self) -> usize {
+        (self.radius - 0.5).ceil().max(0.0) as usize
+    }
+
+    /// Weight of a sample at offset (`dx`, `dy`) pixels from a pixel
+    /// center. Mitchell and Lanczos have negative lobes.
+    pub fn weight(&self, dx: f64, dy: f64) -> f64 {
+        self.weight_1d(dx) * self.weight_1d(dy)
+    }
+
+    fn weight_1d(&self, d: f64) -> f64 {
+        let d = d.abs();
+        if d > self.radius {
+            return 0.0;
+        }
+        // Offset relative to the radius, in [0, 1].
+        let t = d / self.radius;
+
+        match self.kind {
+            FilterType::Box => 1.0,
+            FilterType::Tent => 1.0 - t,
+            FilterType::Gaussian => {
+                // Standard deviation of a third of the radius.
+                const ALPHA: f64 = 4.5;
+                ((-ALPHA * t * t).exp() - (-ALPHA).exp()).max(0.0)
+            }
+            FilterType::Mitchell => mitchell(2.0 * t),
+            FilterType::Lanczos => {
+                const LOBES: f64 = 3.0;
+                sinc(LOBES * t) * sinc(t)
+            }
+        }
+    }
+}
+
+/// Mitchell-Netravali cubic with B = C = 1/3 over [0, 2].
+fn mitchell(x: f64) -> f64 {
+    const B: f64 = 1.0 / 3.0;
+    const C: f64 = 1.0 / 3.0;
+
+    let value = if x < 1.0 {
+        (12.0 - 9.0 * B - 6.0 * C) * x.powi(3)
+            + (-18.0 + 12.0 * B + 6.0 * C) * x * x
+            + (6.0 - 2.0 * B)
+    } else {
+        (-B - 6.0 * C) * x.powi(3)
+            + (6.0 * B + 30.0 * C) * x * x
+            + (-12.0 * B - 48.0 * C) * x
+            + (8.0 * B + 24.0 * C)
+    };
+    value / 6.0
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-5 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [FilterType; 5] = [
+        FilterType::Box,
+        FilterType::Tent,
+        FilterType::Gaussian,
+        FilterType::Mitchell,
+        FilterType::Lanczos,
+    ];
+
+    #[test]
+    fn test_filters_peak_at_center_and_vanish_outside() {
+        for kind in ALL {
+            let filter = Filter::new(kind);
+            let center = filter.weight(0.0, 0.0);
+            assert!(center > 0.0, "{kind:?}");
+
+            for i in 1..20 {
+                let d = i as f64 * filter.radius / 20.0;
+                assert!(filter.weight(d, 0.0) <= center, "{kind:?} at {d}");
+                assert_eq!(filter.weight(d, 0.0), filter.weight(-d, 0.0));
+            }
+            assert_eq!(filter.weight(filter.radius + 0.01, 0.0), 0.0, "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn test_filters_are_continuous_at_the_edge() {
+        for kind in [FilterType::Tent, FilterType::Gaussian, FilterType::Mitchell] {
+            let filter = Filter::new(kind);
+            assert!(
+                filter.weight(filter.radius - 1e-6, 0.0).abs() < 1e-4,
+                "{kind:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_negative_lobes() {
+        let mitchell = Filter::new(FilterType::Mitchell);
+        assert!(mitchell.weight(1.5, 0.0) < 0.0);
+        let lanczos = Filter::new(FilterType::Lanczos);
+        assert!(lanczos.weight(1.0, 0.0) < 0.0);
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        assert_eq!("Gaussian".parse(), Ok(FilterType::Gaussian));
+        assert_eq!("triangle".parse(), Ok(FilterType::Tent));
+        assert!("sinc".parse::<FilterType>().is_err());
+    }
+}
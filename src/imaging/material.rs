@@ -1,17 +1,34 @@
 use crate::{
     geometry::hittable::HitRecord,
+    imaging::{
+        sampler::{Sampler, square_to_sphere},
+        texture::TextureKind,
+    },
     math::{
+        onb::Onb,
         ray::Ray,
-        utils,
         vec3::{Color, Vec3},
     },
 };
+use std::f64::consts::PI;
 
 #[derive(Debug)]
 /// Result of a ray scattering off a material.
 pub struct ScatteredRay {
     pub attenuation: Color,
     pub scattered: Ray,
+    /// Density of the sampled direction, or `None` for specular scattering,
+    /// which cannot be combined with light sampling.
+    pub pdf: Option<f64>,
+}
+
+#[derive(Debug)]
+/// A material's response for a given scattering direction.
+pub struct ScatterEval {
+    /// BSDF value times the cosine term.
+    pub value: Color,
+    /// Density with which `scatter` would have picked the direction.
+    pub pdf: f64,
 }
 
 /// Enum-based material dispatch for better performance.
@@ -20,61 +37,165 @@ pub enum MaterialKind {
     Lambertian(Lambertian),
     Metal(Metal),
     Dielectric(Dielectric),
+    DiffuseLight(DiffuseLight),
+    Isotropic(Isotropic),
+    HenyeyGreenstein(HenyeyGreenstein),
 }
 
 impl MaterialKind {
-    pub fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatteredRay> {
+    pub fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatteredRay> {
+        match self {
+            MaterialKind::Lambertian(m) => m.scatter(r_in, rec, sampler),
+            MaterialKind::Metal(m) => m.scatter(r_in, rec, sampler),
+            MaterialKind::Dielectric(m) => m.scatter(r_in, rec, sampler),
+            MaterialKind::DiffuseLight(m) => m.scatter(r_in, rec, sampler),
+            MaterialKind::Isotropic(m) => m.scatter(r_in, rec, sampler),
+            MaterialKind::HenyeyGreenstein(m) => m.scatter(r_in, rec, sampler),
+        }
+    }
+
+    pub fn emitted(&self, rec: &HitRecord) -> Color {
         match self {
-            MaterialKind::Lambertian(m) => m.scatter(r_in, rec),
-            MaterialKind::Metal(m) => m.scatter(r_in, rec),
-            MaterialKind::Dielectric(m) => m.scatter(r_in, rec),
+            MaterialKind::Lambertian(m) => m.emitted(rec),
+            MaterialKind::Metal(m) => m.emitted(rec),
+            MaterialKind::Dielectric(m) => m.emitted(rec),
+            MaterialKind::DiffuseLight(m) => m.emitted(rec),
+            MaterialKind::Isotropic(m) => m.emitted(rec),
+            MaterialKind::HenyeyGreenstein(m) => m.emitted(rec),
         }
     }
+
+    pub fn evaluate(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3) -> Option<ScatterEval> {
+        match self {
+            MaterialKind::Lambertian(m) => m.evaluate(r_in, rec, direction),
+            MaterialKind::Metal(m) => m.evaluate(r_in, rec, direction),
+            MaterialKind::Dielectric(m) => m.evaluate(r_in, rec, direction),
+            MaterialKind::DiffuseLight(m) => m.evaluate(r_in, rec, direction),
+            MaterialKind::Isotropic(m) => m.evaluate(r_in, rec, direction),
+            MaterialKind::HenyeyGreenstein(m) => m.evaluate(r_in, rec, direction),
+        }
+    }
+
+    pub fn albedo(&self, rec: &HitRecord) -> Color {
+        match self {
+            MaterialKind::Lambertian(m) => m.albedo(rec),
+            MaterialKind::Metal(m) => m.albedo(rec),
+            MaterialKind::Dielectric(m) => m.albedo(rec),
+            MaterialKind::DiffuseLight(m) => m.albedo(rec),
+            MaterialKind::Isotropic(m) => m.albedo(rec),
+            MaterialKind::HenyeyGreenstein(m) => m.albedo(rec),
+        }
+    }
+
+    pub fn is_emissive(&self) -> bool {
+        matches!(self, MaterialKind::DiffuseLight(_))
+    }
 }
 
 /// Trait for materials that can scatter rays.
 pub trait Material {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatteredRay>;
+    /// Samples an outgoing ray, drawing its random dimensions from `sampler`.
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatteredRay>;
+
+    /// Light emitted by the surface at the hit point. Black for non-emitters.
+    fn emitted(&self, _rec: &HitRecord) -> Color {
+        Color::default()
+    }
+
+    /// Evaluates scattering towards `direction`, for light sampling.
+    /// `None` for specular materials and directions they never scatter into.
+    fn evaluate(&self, _r_in: &Ray, _rec: &HitRecord, _direction: Vec3) -> Option<ScatterEval> {
+        None
+    }
+
+    /// Surface color at the hit point, for the albedo AOV. White for
+    /// materials without one.
+    fn albedo(&self, _rec: &HitRecord) -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 /// A diffuse material (matte).
 pub struct Lambertian {
-    pub albedo: Color,
+    pub albedo: TextureKind,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Self {
+        Self::from_texture(albedo.into())
+    }
+
+    pub fn from_texture(albedo: TextureKind) -> Self {
         Self { albedo }
     }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _r_in: &Ray, rec: &HitRecord) -> Option<ScatteredRay> {
-        let mut scatter_direction = rec.normal + Vec3::random_unit_vector();
+    fn scatter(
+        &self,
+        _r_in: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatteredRay> {
+        let mut scatter_direction = rec.normal + square_to_sphere(sampler.next_2d());
 
         if scatter_direction.near_zero() {
             scatter_direction = rec.normal;
         }
 
         let scattered = Ray::new(rec.p, scatter_direction);
+        let pdf = rec.normal.dot(scatter_direction.unit_vector()).max(0.0) / PI;
         Some(ScatteredRay {
-            attenuation: self.albedo,
+            attenuation: self.albedo.value(rec.u, rec.v, rec.p),
             scattered,
+            pdf: Some(pdf),
         })
     }
+
+    fn evaluate(&self, _r_in: &Ray, rec: &HitRecord, direction: Vec3) -> Option<ScatterEval> {
+        let cosine = rec.normal.dot(direction.unit_vector());
+        if cosine <= 0.0 {
+            return None;
+        }
+
+        // Cosine-weighted sampling: the pdf equals the cosine-weighted BSDF.
+        let pdf = cosine / PI;
+        Some(ScatterEval {
+            value: self.albedo.value(rec.u, rec.v, rec.p) * pdf,
+            pdf,
+        })
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color {
+        self.albedo.value(rec.u, rec.v, rec.p)
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 /// A metallic material.
 pub struct Metal {
-    pub albedo: Color,
+    pub albedo: TextureKind,
     pub fuzz: f64,
 }
 
 impl Metal {
     /// Creates a new Metal material with albedo and fuzziness.
     pub fn new(albedo: Color, fuzz: f64) -> Self {
+        Self::from_texture(albedo.into(), fuzz)
+    }
+
+    pub fn from_texture(albedo: TextureKind, fuzz: f64) -> Self {
         Self {
             albedo,
             fuzz: fuzz.clamp(0.0, 1.0),
@@ -83,19 +204,29 @@ impl Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatteredRay> {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatteredRay> {
         let mut reflected = r_in.direction.reflect(rec.normal);
-        reflected = reflected.unit_vector() + Vec3::random_unit_vector() * self.fuzz;
+        reflected = reflected.unit_vector() + square_to_sphere(sampler.next_2d()) * self.fuzz;
         let scattered = Ray::new(rec.p, reflected);
         if scattered.direction.dot(rec.normal) > 0.0 {
             Some(ScatteredRay {
-                attenuation: self.albedo,
+                attenuation: self.albedo.value(rec.u, rec.v, rec.p),
                 scattered,
+                pdf: None,
             })
         } else {
             None
         }
     }
+
+    fn albedo(&self, rec: &HitRecord) -> Color {
+        self.albedo.value(rec.u, rec.v, rec.p)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -117,7 +248,12 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatteredRay> {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatteredRay> {
         let attenuation = Color::new(1.0, 1.0, 1.0);
 
         let refraction_ratio = if rec.front_face {
@@ -132,16 +268,192 @@ impl Material for Dielectric {
 
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
 
-        let direction =
-            if cannot_refract || Self::reflectance(cos_theta, refraction_ratio) > utils::random() {
-                unit_direction.reflect(rec.normal)
-            } else {
-                unit_direction.refract(rec.normal, refraction_ratio)
-            };
+        let direction = if cannot_refract
+            || Self::reflectance(cos_theta, refraction_ratio) > sampler.next_1d()
+        {
+            unit_direction.reflect(rec.normal)
+        } else {
+            unit_direction.refract(rec.normal, refraction_ratio)
+        };
 
         Some(ScatteredRay {
             attenuation,
             scattered: Ray::new(rec.p, direction),
+            pdf: None,
         })
     }
 }
+
+#[derive(Debug, Clone)]
+/// A light-emitting material. It emits from both sides and never scatters.
+pub struct DiffuseLight {
+    pub emit: TextureKind,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        Self::from_texture(emit.into())
+    }
+
+    pub fn from_texture(emit: TextureKind) -> Self {
+        Self { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<ScatteredRay> {
+        None
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        self.emit.value(rec.u, rec.v, rec.p)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A phase function that scatters equally in every direction, for the
+/// inside of participating media such as fog or smoke.
+pub struct Isotropic {
+    pub albedo: TextureKind,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Self::from_texture(albedo.into())
+    }
+
+    pub fn from_texture(albedo: TextureKind) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        _r_in: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatteredRay> {
+        Some(ScatteredRay {
+            attenuation: self.albedo.value(rec.u, rec.v, rec.p),
+            scattered: Ray::new(rec.p, square_to_sphere(sampler.next_2d())),
+            pdf: Some(1.0 / (4.0 * PI)),
+        })
+    }
+
+    fn evaluate(&self, _r_in: &Ray, rec: &HitRecord, _direction: Vec3) -> Option<ScatterEval> {
+        let pdf = 1.0 / (4.0 * PI);
+        Some(ScatterEval {
+            value: self.albedo.value(rec.u, rec.v, rec.p) * pdf,
+            pdf,
+        })
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color {
+        self.albedo.value(rec.u, rec.v, rec.p)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The Henyey–Greenstein phase function: `g` in (-1, 1) skews scattering
+/// forwards (positive, e.g. haze) or backwards (negative); 0 is isotropic.
+pub struct HenyeyGreenstein {
+    pub albedo: TextureKind,
+    pub g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Color, g: f64) -> Self {
+        Self::from_texture(albedo.into(), g)
+    }
+
+    pub fn from_texture(albedo: TextureKind, g: f64) -> Self {
+        Self { albedo, g }
+    }
+
+    /// Density of scattering by an angle whose cosine is `cos_theta`.
+    fn phase(&self, cos_theta: f64) -> f64 {
+        let g = self.g;
+        let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denom * denom.sqrt())
+    }
+
+    /// Inverts the phase function's CDF for the cosine of the angle
+    /// between the incoming and scattered directions.
+    fn sample_cos_theta(&self, u: f64) -> f64 {
+        let g = self.g;
+        if g.abs() < 1e-3 {
+            return 1.0 - 2.0 * u;
+        }
+        let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u);
+        ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatteredRay> {
+        let (u, v) = sampler.next_2d();
+        let cos_theta = self.sample_cos_theta(u);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+        let local = Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+        let direction = Onb::new(r_in.direction).transform(local);
+
+        Some(ScatteredRay {
+            attenuation: self.albedo.value(rec.u, rec.v, rec.p),
+            scattered: Ray::new(rec.p, direction),
+            pdf: Some(self.phase(cos_theta)),
+        })
+    }
+
+    fn evaluate(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3) -> Option<ScatterEval> {
+        let cos_theta = r_in.direction.unit_vector().dot(direction.unit_vector());
+        let pdf = self.phase(cos_theta);
+        Some(ScatterEval {
+            value: self.albedo.value(rec.u, rec.v, rec.p) * pdf,
+            pdf,
+        })
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color {
+        self.albedo.value(rec.u, rec.v, rec.p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_henyey_greenstein_is_normalized_and_skewed() {
+        let phase = HenyeyGreenstein::new(Color::new(1.0, 1.0, 1.0), 0.6);
+
+        // The density integrates to one over the sphere of directions.
+        let n = 100_000;
+        let integral: f64 = (0..n)
+            .map(|i| phase.phase(-1.0 + 2.0 * (i as f64 + 0.5) / n as f64))
+            .sum::<f64>()
+            * 2.0
+            / n as f64
+            * 2.0
+            * PI;
+        assert!((integral - 1.0).abs() < 1e-3, "{integral}");
+
+        // The mean cosine of sampled angles is g.
+        let mean: f64 = (0..n)
+            .map(|i| phase.sample_cos_theta((i as f64 + 0.5) / n as f64))
+            .sum::<f64>()
+            / n as f64;
+        assert!((mean - 0.6).abs() < 1e-3, "{mean}");
+    }
+}
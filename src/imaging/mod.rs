@@ -1,10 +1,26 @@
+pub mod accumulation;
+pub mod aov;
+pub mod atmosphere;
 pub mod camera;
 pub mod canvas;
 pub mod color;
+pub mod denoise;
+pub mod filter;
 pub mod material;
 pub mod renderer;
+pub mod sampler;
+pub mod texture;
+pub mod tonemap;
 
+pub use accumulation::AccumulationBuffer;
+pub use aov::Aov;
+pub use atmosphere::{Atmosphere, DensityProfile};
 pub use camera::Camera;
 pub use canvas::Canvas;
+pub use denoise::Denoiser;
+pub use filter::{Filter, FilterType};
 pub use material::Material;
-pub use renderer::Renderer;
+pub use renderer::{AdaptiveSampling, Integrator, Renderer};
+pub use sampler::{Sampler, SamplerKind, SamplerType};
+pub use texture::{Texture, TextureKind};
+pub use tonemap::{ToneMap, ToneMapOperator};
@@ -1,16 +1,113 @@
-use indicatif::{ParallelProgressIterator, ProgressBar, ProgressStyle};
+use std::{
+    str::FromStr,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::*;
 
 use crate::{
-    geometry::{hittable::Hittable, hittable_list::HittableList},
-    imaging::{camera::Camera, canvas::Canvas},
+    geometry::{
+        hittable::{HitRecord, Hittable},
+        hittable_list::HittableList,
+    },
+    imaging::{
+        accumulation::{AccumulationBuffer, FilmBand, PixelStats},
+        aov::{AovStats, SurfaceSample},
+        atmosphere::Atmosphere,
+        camera::Camera,
+        canvas::Canvas,
+        filter::Filter,
+        sampler::{Sampler, SamplerType},
+    },
     math::{interval::Interval, ray::Ray, utils, vec3::Color},
     scene::Background,
 };
 
+/// Light transport algorithm used to estimate each sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Integrator {
+    /// Plain path tracing: light is only found when a scattered ray hits it.
+    Naive,
+    /// Path tracing with direct light sampling, combined with BSDF sampling
+    /// through multiple importance sampling.
+    #[default]
+    NextEvent,
+}
+
+impl FromStr for Integrator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "naive" => Ok(Integrator::Naive),
+            "nee" | "next-event" | "mis" => Ok(Integrator::NextEvent),
+            _ => Err(format!(
+                "unknown integrator '{s}' (expected 'naive' or 'nee')"
+            )),
+        }
+    }
+}
+
+/// Per-pixel sample budget driven by the estimated noise: after
+/// `min_samples`, a pixel stops once the standard error of its mean
+/// luminance falls below `threshold` times that mean.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveSampling {
+    pub min_samples: u32,
+    pub threshold: f64,
+}
+
+impl AdaptiveSampling {
+    /// Luminance below which pixels are judged on absolute rather than
+    /// relative error, so near-black pixels do not sample forever.
+    const DARK_LUMINANCE: f64 = 0.01;
+
+    fn is_converged(&self, stats: &PixelStats) -> bool {
+        stats.count() >= self.min_samples.max(2)
+            && stats.standard_error()
+                <= self.threshold * stats.mean_luminance().max(Self::DARK_LUMINANCE)
+    }
+}
+
+/// Rows rendered together, splatting into one shared [`FilmBand`].
+const STRIPE_ROWS: usize = 8;
+
+/// Everything a ray can interact with while rendering one frame.
+struct RenderContext<'a> {
+    world: &'a (dyn Hittable + Sync),
+    lights: &'a HittableList,
+    background: &'a Background,
+    atmosphere: Option<&'a Atmosphere>,
+}
+
+#[derive(Debug, Clone)]
 pub struct Renderer {
+    /// Samples per pixel taken by `render`. Passes split this budget, and
+    /// the stratified sampler spreads its strata over all of it.
     pub samples_per_pixel: u32,
     pub max_depth: u32,
+    pub integrator: Integrator,
+    /// Bounces after which paths are subject to Russian roulette. Set it to
+    /// `max_depth` or more to disable roulette.
+    pub roulette_depth: u32,
+    pub sampler: SamplerType,
+    /// Spreads every sample over the pixels within its radius.
+    pub filter: Filter,
+    /// Fog filling the whole scene, met by every ray on its way.
+    pub atmosphere: Option<Atmosphere>,
+    /// Stops sampling converged pixels early; `samples_per_pixel` is then
+    /// the maximum.
+    pub adaptive: Option<AdaptiveSampling>,
+    /// Base seed; every pixel sample draws from its own stream derived
+    /// from it, so output is identical across runs and thread counts.
+    pub seed: u64,
+    /// Set from another thread (e.g. a Ctrl-C handler) to stop rendering
+    /// after the rows in flight, leaving the remaining pixels unsampled.
+    pub interrupt: Option<Arc<AtomicBool>>,
 }
 
 impl Renderer {
@@ -18,18 +115,62 @@ impl Renderer {
         Self {
             samples_per_pixel,
             max_depth,
+            integrator: Integrator::default(),
+            roulette_depth: 3,
+            sampler: SamplerType::default(),
+            filter: Filter::default(),
+            atmosphere: None,
+            adaptive: None,
+            seed: 0,
+            interrupt: None,
         }
     }
 
+    /// Renders into `canvas` and returns the number of samples taken for
+    /// each pixel.
     pub fn render(
         &self,
-        world: &HittableList,
+        world: &(dyn Hittable + Sync),
+        lights: &HittableList,
         camera: &Camera,
         canvas: &mut Canvas,
         background: &Background,
-    ) {
-        let width = canvas.width as usize;
-        let height = canvas.height as usize;
+    ) -> Vec<u32> {
+        let mut buffer = AccumulationBuffer::new(canvas.width, canvas.height);
+        self.render_pass(
+            world,
+            lights,
+            camera,
+            background,
+            &mut buffer,
+            self.samples_per_pixel,
+        );
+
+        *canvas = buffer.to_canvas();
+        buffer.sample_counts()
+    }
+
+    /// Adds up to `samples` samples to every pixel of `buffer`, continuing
+    /// each pixel's sample sequence where the previous pass left it, and
+    /// returns the number of samples taken. Pixels that adaptive sampling
+    /// deems converged are skipped.
+    pub fn render_pass(
+        &self,
+        world: &(dyn Hittable + Sync),
+        lights: &HittableList,
+        camera: &Camera,
+        background: &Background,
+        buffer: &mut AccumulationBuffer,
+        samples: u32,
+    ) -> u64 {
+        let width = buffer.width as usize;
+        let height = buffer.height as usize;
+        let ctx = RenderContext {
+            world,
+            lights,
+            background,
+            atmosphere: self.atmosphere.as_ref(),
+        };
 
         let pb = ProgressBar::new(height as u64);
         pb.set_style(
@@ -39,50 +180,291 @@ impl Renderer {
                 .progress_chars("##-"),
         );
 
-        canvas
-            .pixels_mut()
-            .par_chunks_mut(width)
-            .progress_with(pb)
+        let stripe_len = width * STRIPE_ROWS;
+        let stripe_count = height.div_ceil(STRIPE_ROWS);
+        let bands: Vec<FilmBand> = (0..stripe_count)
+            .map(|s| {
+                buffer.film_band(
+                    s * STRIPE_ROWS..((s + 1) * STRIPE_ROWS).min(height),
+                    &self.filter,
+                )
+            })
+            .collect();
+
+        let (pixels, aovs) = buffer.pixels_and_aovs_mut();
+        let aov_stripes: Vec<Option<&mut [AovStats]>> = if aovs.is_empty() {
+            (0..stripe_count).map(|_| None).collect()
+        } else {
+            aovs.chunks_mut(stripe_len).map(Some).collect()
+        };
+
+        let stripes: Vec<(u64, FilmBand)> = pixels
+            .par_chunks_mut(stripe_len)
+            .zip(aov_stripes)
+            .zip(bands)
             .enumerate()
-            .for_each(|(j, row)| {
-                for (i, pixel) in row.iter_mut().enumerate() {
-                    let mut pixel_color = Color::default();
-
-                    for _ in 0..self.samples_per_pixel {
-                        let r_i = i as f64 + utils::random_range(-0.5, 0.5);
-                        let r_j = j as f64 + utils::random_range(-0.5, 0.5);
-                        let u = r_i / (width as f64);
-                        let v = r_j / (height as f64);
-
-                        let ray = camera.get_ray(u, v);
-                        pixel_color += self.ray_color(&ray, self.max_depth, world, background);
+            .map(|(s, ((stripe, mut aov_stripe), mut band))| {
+                let rows = stripe.len() / width;
+                let mut taken = 0;
+                for (r, row) in stripe.chunks_mut(width).enumerate() {
+                    if self.is_interrupted() {
+                        break;
                     }
+                    let aov_row = aov_stripe
+                        .as_deref_mut()
+                        .map(|aovs| &mut aovs[r * width..(r + 1) * width]);
+                    let j = s * STRIPE_ROWS + r;
+                    taken += self.render_row(
+                        camera,
+                        &ctx,
+                        (j, row, aov_row),
+                        height,
+                        &mut band,
+                        samples,
+                    );
+                }
+                pb.inc(rows as u64);
+                (taken, band)
+            })
+            .collect();
+        pb.finish();
+
+        // Merging in stripe order keeps the film identical across runs.
+        let mut total = 0;
+        for (taken, band) in &stripes {
+            buffer.merge_band(band);
+            total += taken;
+        }
+        total
+    }
+
+    /// Adds up to `samples` samples to every pixel of row `j`, splatting
+    /// them into `band`, and returns the number taken.
+    fn render_row(
+        &self,
+        camera: &Camera,
+        ctx: &RenderContext,
+        (j, row, mut aov_row): (usize, &mut [PixelStats], Option<&mut [AovStats]>),
+        height: usize,
+        band: &mut FilmBand,
+        samples: u32,
+    ) -> u64 {
+        let width = row.len();
+        let mut sampler = self.sampler.build(self.seed, self.samples_per_pixel);
+        let mut taken = 0;
 
-                    let scale = 1.0 / self.samples_per_pixel as f64;
-                    *pixel = pixel_color * scale;
+        for (i, stats) in row.iter_mut().enumerate() {
+            let pixel_index = (j * width + i) as u64;
+            let first = stats.count();
+
+            for sample in first..first.saturating_add(samples) {
+                if self.adaptive.is_some_and(|a| a.is_converged(stats)) {
+                    break;
                 }
-            });
+
+                // Light sampling still draws from the thread-local
+                // generator, so give it a stream per sample as well.
+                utils::reseed(utils::stream_seed(self.seed, &[pixel_index, sample as u64]));
+                sampler.start_sample(pixel_index, sample);
+
+                // Raster position, with pixel centers on integers.
+                let (dx, dy) = sampler.next_2d();
+                let x = i as f64 + dx - 0.5;
+                let y = j as f64 + dy - 0.5;
+
+                let ray = camera.get_ray(x / width as f64, y / height as f64, &mut sampler);
+                let path = match self.integrator {
+                    Integrator::Naive => self.ray_color(&ray, ctx, &mut sampler),
+                    Integrator::NextEvent => self.ray_color_mis(&ray, ctx, &mut sampler),
+                };
+                stats.add(path.color);
+                band.splat(&self.filter, (x, y), path.color, path.direct);
+                if let Some(aov_row) = aov_row.as_deref_mut() {
+                    aov_row[i].add(path.surface.as_ref());
+                }
+                taken += 1;
+            }
+        }
+
+        taken
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupt
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::Relaxed))
     }
 
-    fn ray_color(
+    fn ray_color(&self, ray: &Ray, ctx: &RenderContext, sampler: &mut dyn Sampler) -> PathSample {
+        let mut ray = *ray;
+        let mut path = PathSample::default();
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+
+        for bounce in 0..self.max_depth {
+            let Some(rec) = Self::trace(&ray, ctx, sampler) else {
+                path.add(
+                    throughput * Self::background_color(&ray, ctx.background),
+                    bounce,
+                );
+                break;
+            };
+            if bounce == 0 {
+                path.surface = Some(surface_sample(&ray, &rec));
+            }
+
+            path.add(throughput * rec.material.emitted(&rec), bounce);
+
+            let Some(scattered) = rec.material.scatter(&ray, &rec, sampler) else {
+                break;
+            };
+            throughput *= scattered.attenuation;
+            ray = scattered.scattered;
+
+            if !self.survives_roulette(bounce, &mut throughput, sampler) {
+                break;
+            }
+        }
+
+        path
+    }
+
+    /// Path tracing with next-event estimation. Emission found by a BSDF
+    /// sampled bounce is weighted against light sampling; camera rays and
+    /// specular bounces, which are not light sampled, keep all of it.
+    fn ray_color_mis(
         &self,
         ray: &Ray,
-        depth: u32,
-        world: &HittableList,
-        background: &Background,
-    ) -> Color {
-        if depth == 0 {
-            return Color::new(0.0, 0.0, 0.0);
-        }
+        ctx: &RenderContext,
+        sampler: &mut dyn Sampler,
+    ) -> PathSample {
+        let mut ray = *ray;
+        let mut path = PathSample::default();
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        // Density with which the previous bounce sampled `ray`, or `None`
+        // if it was not light sampled.
+        let mut bsdf_pdf: Option<f64> = None;
 
-        if let Some(rec) = world.hit(ray, Interval::new(0.001, f64::INFINITY)) {
-            if let Some(scattered) = rec.material.scatter(ray, &rec) {
-                return scattered.attenuation
-                    * self.ray_color(&scattered.scattered, depth - 1, world, background);
+        for bounce in 0..self.max_depth {
+            let Some(rec) = Self::trace(&ray, ctx, sampler) else {
+                path.add(
+                    throughput * Self::background_color(&ray, ctx.background),
+                    bounce,
+                );
+                break;
+            };
+            if bounce == 0 {
+                path.surface = Some(surface_sample(&ray, &rec));
+            }
+
+            if rec.material.is_emissive() {
+                let weight = match bsdf_pdf {
+                    Some(bsdf_pdf) if !ctx.lights.is_empty() => {
+                        let light_pdf = ctx.lights.pdf_value(ray.origin, ray.direction);
+                        power_heuristic(bsdf_pdf, light_pdf)
+                    }
+                    _ => 1.0,
+                };
+                path.add(throughput * rec.material.emitted(&rec) * weight, bounce);
+            }
+
+            let Some(scattered) = rec.material.scatter(&ray, &rec, sampler) else {
+                break;
+            };
+
+            if scattered.pdf.is_some() && !ctx.lights.is_empty() {
+                path.add(
+                    throughput * Self::sample_lights(&ray, &rec, ctx),
+                    bounce + 1,
+                );
+            }
+            bsdf_pdf = scattered.pdf;
+            throughput *= scattered.attenuation;
+            ray = scattered.scattered;
+
+            if !self.survives_roulette(bounce, &mut throughput, sampler) {
+                break;
             }
-            return Color::new(0.0, 0.0, 0.0);
         }
 
+        path
+    }
+
+    /// The closest interaction along `ray`: a surface, or the point where
+    /// the atmosphere scatters the ray if that comes first. Rays that pass
+    /// through unscattered need no reweighting, as the chance of getting
+    /// through is the atmosphere's transmittance.
+    fn trace<'a>(
+        ray: &Ray,
+        ctx: &RenderContext<'a>,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'a>> {
+        let rec = ctx.world.hit(ray, Interval::new(0.001, f64::INFINITY));
+        let Some(atmosphere) = ctx.atmosphere else {
+            return rec;
+        };
+
+        let t_max = rec.as_ref().map_or(f64::INFINITY, |rec| rec.t);
+        match atmosphere.sample_distance(ray, t_max, sampler.next_1d()) {
+            Some(t) => Some(atmosphere.scatter_record(ray, t)),
+            None => rec,
+        }
+    }
+
+    /// Russian roulette: once `roulette_depth` bounces are done, the path
+    /// continues with a probability that follows its throughput, and the
+    /// survivors are reweighted so that the estimate stays unbiased.
+    fn survives_roulette(
+        &self,
+        bounce: u32,
+        throughput: &mut Color,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        if bounce + 1 < self.roulette_depth {
+            return true;
+        }
+
+        let probability = throughput.x.max(throughput.y).max(throughput.z).min(0.95);
+        if sampler.next_1d() >= probability {
+            return false;
+        }
+        *throughput = *throughput / probability;
+        true
+    }
+
+    /// Estimates direct lighting at a hit by sampling a direction towards a
+    /// light and tracing a shadow ray along it.
+    fn sample_lights(ray: &Ray, rec: &HitRecord, ctx: &RenderContext) -> Color {
+        let direction = ctx.lights.random(rec.p);
+        let light_pdf = ctx.lights.pdf_value(rec.p, direction);
+        if light_pdf <= 0.0 {
+            return Color::default();
+        }
+
+        let Some(eval) = rec.material.evaluate(ray, rec, direction) else {
+            return Color::default();
+        };
+
+        // The shadow ray sees whatever is closest along the direction, so
+        // occluders block the light and only emitters contribute.
+        let shadow_ray = Ray::new(rec.p, direction);
+        let Some(light_rec) = ctx
+            .world
+            .hit(&shadow_ray, Interval::new(0.001, f64::INFINITY))
+        else {
+            return Color::default();
+        };
+
+        // Fog along the way dims the light, without blocking it outright.
+        let transmittance = ctx.atmosphere.map_or(1.0, |atmosphere| {
+            atmosphere.transmittance(&shadow_ray, light_rec.t)
+        });
+        let emitted = light_rec.material.emitted(&light_rec) * transmittance;
+        let weight = power_heuristic(light_pdf, eval.pdf);
+
+        emitted * eval.value * (weight / light_pdf)
+    }
+
+    fn background_color(ray: &Ray, background: &Background) -> Color {
         let unit_direction = ray.direction.unit_vector();
         let u = 0.5 * (unit_direction.x + 1.0);
         let v = 0.5 * (unit_direction.y + 1.0);
@@ -90,3 +472,521 @@ impl Renderer {
         background.color_at(u, v)
     }
 }
+
+/// Light carried to the camera along one path.
+#[derive(Debug, Default)]
+struct PathSample {
+    color: Color,
+    /// The part of `color` that was scattered at most once on the way.
+    direct: Color,
+    /// The camera ray's first hit, if any.
+    surface: Option<SurfaceSample>,
+}
+
+impl PathSample {
+    fn add(&mut self, light: Color, bounces: u32) {
+        self.color += light;
+        if bounces <= 1 {
+            self.direct += light;
+        }
+    }
+}
+
+fn surface_sample(ray: &Ray, rec: &HitRecord) -> SurfaceSample {
+    SurfaceSample {
+        depth: (rec.p - ray.origin).length(),
+        normal: rec.normal,
+        albedo: rec.material.albedo(rec),
+        object_id: rec.object_id,
+        material_id: rec.material_id,
+    }
+}
+
+/// Veach's power heuristic (beta = 2) weight for a sample drawn from `f`
+/// when `g` could have produced it too.
+fn power_heuristic(f_pdf: f64, g_pdf: f64) -> f64 {
+    let f2 = f_pdf * f_pdf;
+    let g2 = g_pdf * g_pdf;
+    if f2 + g2 == 0.0 { 0.0 } else { f2 / (f2 + g2) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geometry::sphere::Sphere,
+        imaging::aov::Aov,
+        imaging::atmosphere::DensityProfile,
+        imaging::filter::FilterType,
+        imaging::material::{
+            DiffuseLight, HenyeyGreenstein, Isotropic, Lambertian, MaterialKind, Metal,
+        },
+        math::{aabb::Aabb, vec3::Vec3},
+    };
+
+    struct TestScene {
+        world: HittableList,
+        lights: HittableList,
+        camera: Camera,
+        background: Background,
+    }
+
+    fn test_scene() -> TestScene {
+        let mut world = HittableList::default();
+        world.add(Sphere::new(
+            Vec3::new(0.0, -100.5, -1.0),
+            100.0,
+            MaterialKind::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        ));
+        world.add(Sphere::new(
+            Vec3::new(0.0, 0.0, -1.0),
+            0.5,
+            MaterialKind::Metal(Metal::new(Color::new(0.8, 0.6, 0.2), 0.3)),
+        ));
+        world.add(Sphere::new(
+            Vec3::new(1.0, 1.0, -1.0),
+            0.3,
+            MaterialKind::DiffuseLight(DiffuseLight::new(Color::new(4.0, 4.0, 4.0))),
+        ));
+        let lights = world.lights();
+
+        let camera = Camera::builder()
+            .aspect_ratio(2.0)
+            .look_from(Vec3::new(0.0, 0.0, 1.0))
+            .look_at(Vec3::new(0.0, 0.0, -1.0))
+            .vup(Vec3::new(0.0, 1.0, 0.0))
+            .fov(60.0)
+            .defocus_angle(1.0)
+            .focus_dist(2.0)
+            .build();
+        let background = Background::Solid {
+            color: Color::new(0.2, 0.3, 0.5),
+        };
+
+        TestScene {
+            world,
+            lights,
+            camera,
+            background,
+        }
+    }
+
+    fn render_with_threads(
+        renderer: &Renderer,
+        scene: &TestScene,
+        threads: usize,
+    ) -> (Vec<Color>, Vec<u32>) {
+        let mut canvas = Canvas::new(16, 2.0);
+        let counts = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap()
+            .install(|| {
+                renderer.render(
+                    &scene.world,
+                    &scene.lights,
+                    &scene.camera,
+                    &mut canvas,
+                    &scene.background,
+                )
+            });
+        (canvas.pixels().to_vec(), counts)
+    }
+
+    #[test]
+    fn test_output_is_independent_of_thread_count() {
+        let scene = test_scene();
+        for sampler in [
+            SamplerType::Independent,
+            SamplerType::Stratified,
+            SamplerType::Halton,
+            SamplerType::Sobol,
+        ] {
+            let mut renderer = Renderer::new(4, 8);
+            renderer.seed = 42;
+            renderer.sampler = sampler;
+            let single = render_with_threads(&renderer, &scene, 1);
+            assert_eq!(
+                single,
+                render_with_threads(&renderer, &scene, 4),
+                "{sampler:?}"
+            );
+            assert_eq!(
+                single,
+                render_with_threads(&renderer, &scene, 3),
+                "{sampler:?}"
+            );
+
+            renderer.seed = 43;
+            assert_ne!(
+                single,
+                render_with_threads(&renderer, &scene, 4),
+                "{sampler:?}"
+            );
+        }
+    }
+
+    fn average(pixels: &[Color]) -> Color {
+        pixels.iter().fold(Color::default(), |sum, &c| sum + c) / pixels.len() as f64
+    }
+
+    #[test]
+    fn test_next_event_agrees_with_naive() {
+        // Light sampling only changes the noise, not the expected image.
+        let scene = test_scene();
+        let mut renderer = Renderer::new(512, 16);
+        renderer.seed = 11;
+
+        renderer.integrator = Integrator::Naive;
+        let (naive, _) = render_with_threads(&renderer, &scene, 2);
+        renderer.integrator = Integrator::NextEvent;
+        let (next_event, _) = render_with_threads(&renderer, &scene, 2);
+
+        let (naive, next_event) = (average(&naive), average(&next_event));
+        assert!(
+            (naive - next_event).length() < 0.03 * naive.length(),
+            "{naive} vs {next_event}"
+        );
+    }
+
+    #[test]
+    fn test_power_heuristic_weights() {
+        assert_eq!(power_heuristic(0.0, 0.0), 0.0);
+        assert_eq!(power_heuristic(2.0, 0.0), 1.0);
+        assert_eq!(power_heuristic(0.0, 2.0), 0.0);
+        let sum = power_heuristic(0.3, 1.7) + power_heuristic(1.7, 0.3);
+        assert!((sum - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_emitters_light_a_closed_room() {
+        // Walls all around the camera, so only the lamp can light them.
+        let room = |lamp: bool| {
+            let mut world = HittableList::default();
+            world.add(Sphere::new(
+                Vec3::new(0.0, 0.0, 0.0),
+                5.0,
+                MaterialKind::Lambertian(Lambertian::new(Color::new(0.7, 0.7, 0.7))),
+            ));
+            if lamp {
+                world.add(Sphere::new(
+                    Vec3::new(0.5, 1.0, -2.0),
+                    0.5,
+                    MaterialKind::DiffuseLight(DiffuseLight::new(Color::new(10.0, 10.0, 10.0))),
+                ));
+            }
+            TestScene {
+                lights: world.lights(),
+                world,
+                background: Background::Solid {
+                    color: Color::default(),
+                },
+                ..test_scene()
+            }
+        };
+
+        for integrator in [Integrator::Naive, Integrator::NextEvent] {
+            let mut renderer = Renderer::new(16, 8);
+            renderer.integrator = integrator;
+
+            let (dark, _) = render_with_threads(&renderer, &room(false), 2);
+            assert!(dark.iter().all(|&c| c == Color::default()));
+
+            let (lit, _) = render_with_threads(&renderer, &room(true), 2);
+            let color = average(&lit);
+            assert!(color.x > 0.05, "{integrator:?}: {color}");
+            assert!(lit.iter().all(|c| c.x.is_finite() && c.x >= 0.0));
+        }
+    }
+
+    #[test]
+    fn test_filtered_output_is_independent_of_thread_count() {
+        let scene = test_scene();
+        let render = |renderer: &Renderer, threads| {
+            // Tall enough for several stripes, so splats cross between them.
+            let mut canvas = Canvas::new(40, 2.0);
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| {
+                    renderer.render(
+                        &scene.world,
+                        &scene.lights,
+                        &scene.camera,
+                        &mut canvas,
+                        &scene.background,
+                    )
+                });
+            canvas.pixels().to_vec()
+        };
+
+        let mut renderer = Renderer::new(2, 4);
+        let unfiltered = render(&renderer, 1);
+        for kind in [FilterType::Gaussian, FilterType::Lanczos] {
+            renderer.filter = Filter::new(kind);
+            let single = render(&renderer, 1);
+            assert_eq!(single, render(&renderer, 3), "{kind:?}");
+            assert_ne!(single, unfiltered, "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn test_adaptive_sampling_spends_samples_on_noisy_pixels() {
+        let scene = test_scene();
+        let mut renderer = Renderer::new(64, 8);
+        renderer.adaptive = Some(AdaptiveSampling {
+            min_samples: 8,
+            threshold: 0.05,
+        });
+        let (_, counts) = render_with_threads(&renderer, &scene, 2);
+
+        assert!(counts.iter().all(|&n| (8..=64).contains(&n)));
+        // The top corners only see the flat background.
+        assert_eq!(counts[0], 8);
+        assert_eq!(counts[15], 8);
+        assert!(counts.contains(&64));
+
+        renderer.adaptive = None;
+        let (_, counts) = render_with_threads(&renderer, &scene, 2);
+        assert!(counts.iter().all(|&n| n == 64));
+    }
+
+    #[test]
+    fn test_passes_continue_the_sample_sequence() {
+        let scene = test_scene();
+        for sampler in [
+            SamplerType::Independent,
+            SamplerType::Stratified,
+            SamplerType::Halton,
+            SamplerType::Sobol,
+        ] {
+            let mut renderer = Renderer::new(8, 8);
+            renderer.sampler = sampler;
+            let render_pass = |buffer: &mut AccumulationBuffer, samples| {
+                renderer.render_pass(
+                    &scene.world,
+                    &scene.lights,
+                    &scene.camera,
+                    &scene.background,
+                    buffer,
+                    samples,
+                )
+            };
+
+            let mut single = AccumulationBuffer::new(16, 8);
+            assert_eq!(render_pass(&mut single, 8), 16 * 8 * 8);
+
+            let mut split = AccumulationBuffer::new(16, 8);
+            render_pass(&mut split, 3);
+            render_pass(&mut split, 5);
+
+            for (a, b) in single
+                .to_canvas()
+                .pixels()
+                .iter()
+                .zip(split.to_canvas().pixels())
+            {
+                assert!((*a - *b).length() < 1e-9, "{sampler:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_interrupt_stops_sampling() {
+        let scene = test_scene();
+        let mut renderer = Renderer::new(4, 8);
+        renderer.interrupt = Some(Arc::new(AtomicBool::new(true)));
+        let (_, counts) = render_with_threads(&renderer, &scene, 2);
+        assert!(counts.iter().all(|&n| n == 0));
+    }
+
+    #[test]
+    fn test_russian_roulette_is_unbiased() {
+        let scene = test_scene();
+        let mut renderer = Renderer::new(256, 16);
+        renderer.roulette_depth = 16;
+        let (full, _) = render_with_threads(&renderer, &scene, 2);
+
+        renderer.roulette_depth = 1;
+        let (roulette, _) = render_with_threads(&renderer, &scene, 2);
+
+        let (full, roulette) = (average(&full), average(&roulette));
+        assert!(
+            (full - roulette).length() < 0.02 * full.length(),
+            "{full} vs {roulette}"
+        );
+    }
+
+    /// The camera inside a glowing sphere of radius 10, filled with fog of
+    /// density 0.1 whose phase function has the given albedo.
+    fn foggy_furnace(albedo: f64) -> (TestScene, Atmosphere) {
+        let mut world = HittableList::default();
+        world.add(Sphere::new(
+            Vec3::new(0.0, 0.0, 1.0),
+            10.0,
+            MaterialKind::DiffuseLight(DiffuseLight::new(Color::new(1.0, 1.0, 1.0))),
+        ));
+        let scene = TestScene {
+            lights: world.lights(),
+            world,
+            ..test_scene()
+        };
+        let phase = MaterialKind::HenyeyGreenstein(HenyeyGreenstein::new(
+            Color::new(albedo, albedo, albedo),
+            0.5,
+        ));
+        (
+            scene,
+            Atmosphere::new(0.1, DensityProfile::Homogeneous, phase),
+        )
+    }
+
+    #[test]
+    fn test_atmosphere_absorbs_along_camera_rays() {
+        // Black fog lets e⁻¹ of the light through ten units.
+        let (scene, atmosphere) = foggy_furnace(0.0);
+        let mut renderer = Renderer::new(64, 8);
+        renderer.atmosphere = Some(atmosphere);
+        let (pixels, _) = render_with_threads(&renderer, &scene, 2);
+        let expected = (-1.0f64).exp();
+        let color = average(&pixels);
+        assert!((color.x - expected).abs() < 0.02, "{color}");
+    }
+
+    #[test]
+    fn test_atmosphere_in_scattering_conserves_energy() {
+        // White fog in a uniformly glowing enclosure only redirects light,
+        // so every pixel still sees a radiance of one.
+        let (scene, atmosphere) = foggy_furnace(1.0);
+        for integrator in [Integrator::Naive, Integrator::NextEvent] {
+            let mut renderer = Renderer::new(64, 64);
+            renderer.integrator = integrator;
+            renderer.atmosphere = Some(atmosphere.clone());
+            let (pixels, _) = render_with_threads(&renderer, &scene, 2);
+            let color = average(&pixels);
+            assert!((color.x - 1.0).abs() < 0.03, "{integrator:?}: {color}");
+        }
+    }
+
+    #[test]
+    fn test_sky_shows_through_bounded_fog() {
+        // Lit only by the background: the fog must end somewhere for any
+        // light to get in.
+        let mut world = HittableList::default();
+        world.add(Sphere::new(
+            Vec3::new(0.0, -100.5, -1.0),
+            100.0,
+            MaterialKind::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        ));
+        let scene = TestScene {
+            lights: HittableList::default(),
+            world,
+            ..test_scene()
+        };
+        let phase = MaterialKind::Isotropic(Isotropic::new(Color::new(0.9, 0.9, 0.9)));
+        let fog = Atmosphere::new(0.05, DensityProfile::Homogeneous, phase);
+
+        let mut renderer = Renderer::new(16, 16);
+        let (clear, _) = render_with_threads(&renderer, &scene, 2);
+        renderer.atmosphere = Some(fog.clone());
+        let (unbounded, _) = render_with_threads(&renderer, &scene, 2);
+        let bounds = Aabb::from_points(Vec3::new(-5.0, -5.0, -5.0), Vec3::new(5.0, 5.0, 5.0));
+        renderer.atmosphere = Some(fog.with_bounds(bounds));
+        let (bounded, _) = render_with_threads(&renderer, &scene, 2);
+
+        assert!(unbounded.iter().all(|&c| c == Color::default()));
+        assert!(bounded.iter().all(|c| c.x > 0.0));
+        // Half a unit of optical depth at most, so the sky is dimmed but
+        // far from gone.
+        let (clear, bounded) = (average(&clear), average(&bounded));
+        assert!(bounded.z > 0.5 * clear.z, "{bounded} vs {clear}");
+    }
+
+    #[test]
+    fn test_integrators_agree_in_fog() {
+        // Light sampling must account for the fog between a scattering
+        // point and the light, or it finds more light than plain paths do.
+        let scene = test_scene();
+        let phase = MaterialKind::Isotropic(Isotropic::new(Color::new(0.8, 0.8, 0.8)));
+        let mut renderer = Renderer::new(512, 16);
+        renderer.atmosphere = Some(Atmosphere::new(0.5, DensityProfile::Homogeneous, phase));
+
+        renderer.integrator = Integrator::Naive;
+        let (naive, _) = render_with_threads(&renderer, &scene, 2);
+        renderer.integrator = Integrator::NextEvent;
+        let (next_event, _) = render_with_threads(&renderer, &scene, 2);
+
+        let (naive, next_event) = (average(&naive), average(&next_event));
+        assert!(
+            (naive - next_event).length() < 0.03 * naive.length(),
+            "{naive} vs {next_event}"
+        );
+    }
+
+    #[test]
+    fn test_deep_paths_do_not_overflow_the_stack() {
+        // A perfect mirror enclosing the camera traps every path.
+        let mut world = HittableList::default();
+        world.add(Sphere::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            10.0,
+            MaterialKind::Metal(Metal::new(Color::new(1.0, 1.0, 1.0), 0.0)),
+        ));
+        let scene = TestScene {
+            lights: world.lights(),
+            world,
+            ..test_scene()
+        };
+
+        let mut renderer = Renderer::new(1, 100_000);
+        renderer.roulette_depth = u32::MAX;
+        renderer.integrator = Integrator::Naive;
+        let mut canvas = Canvas::new(1, 1.0);
+        renderer.render(
+            &scene.world,
+            &scene.lights,
+            &scene.camera,
+            &mut canvas,
+            &scene.background,
+        );
+        assert_eq!(canvas.pixels()[0], Color::default());
+    }
+
+    #[test]
+    fn test_aovs_split_the_beauty_render() {
+        let scene = test_scene();
+        let mut renderer = Renderer::new(4, 8);
+        // Lighting must be filtered like the beauty render to add up to it.
+        for filter in [FilterType::Box, FilterType::Gaussian] {
+            renderer.filter = Filter::new(filter);
+            let mut buffer = AccumulationBuffer::new(16, 8).with_aovs();
+            renderer.render_pass(
+                &scene.world,
+                &scene.lights,
+                &scene.camera,
+                &scene.background,
+                &mut buffer,
+                4,
+            );
+
+            let beauty = buffer.to_canvas();
+            let direct = buffer.aov_canvas(Aov::Direct).unwrap();
+            let indirect = buffer.aov_canvas(Aov::Indirect).unwrap();
+            for i in 0..beauty.pixels().len() {
+                let sum = direct.pixels()[i] + indirect.pixels()[i];
+                assert!((sum - beauty.pixels()[i]).length() < 1e-9, "{filter:?}");
+            }
+
+            // The top row sees only the background, the bottom row the ground.
+            let depth = buffer.aov_canvas(Aov::Depth).unwrap();
+            assert_eq!(depth.pixels()[0], Color::default());
+            assert!(depth.pixels()[16 * 7].x > 0.0);
+        }
+        assert!(
+            AccumulationBuffer::new(16, 8)
+                .aov_canvas(Aov::Depth)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,496 @@
+use std::{f64::consts::PI, str::FromStr};
+
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+use serde::Deserialize;
+
+use crate::math::{utils, vec3::Vec3};
+
+/// Source of the random numbers that drive one pixel sample: the position
+/// inside the pixel, the lens position and every scattering decision.
+///
+/// Each call consumes the next dimension of the current sample. Samplers
+/// other than [`IndependentSampler`] spread the samples of a pixel evenly
+/// over every dimension, which converges faster than pure random numbers.
+pub trait Sampler {
+    /// Starts sample `index` of the pixel with linear index `pixel`.
+    fn start_sample(&mut self, pixel: u64, index: u32);
+
+    /// Returns the next dimension, in [0, 1).
+    fn next_1d(&mut self) -> f64;
+
+    /// Returns the next two dimensions as a point in the unit square.
+    fn next_2d(&mut self) -> (f64, f64);
+}
+
+/// Sampler selection, as given on the command line or in a scene file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum SamplerType {
+    Independent,
+    Stratified,
+    Halton,
+    #[default]
+    Sobol,
+}
+
+impl FromStr for SamplerType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "independent" | "random" => Ok(SamplerType::Independent),
+            "stratified" | "jittered" => Ok(SamplerType::Stratified),
+            "halton" => Ok(SamplerType::Halton),
+            "sobol" => Ok(SamplerType::Sobol),
+            _ => Err(format!(
+                "unknown sampler '{s}' (expected 'independent', 'stratified', 'halton' or 'sobol')"
+            )),
+        }
+    }
+}
+
+impl SamplerType {
+    /// Creates a sampler for `samples_per_pixel` samples. All of its output
+    /// is a pure function of `seed`, the pixel and the sample index.
+    pub fn build(self, seed: u64, samples_per_pixel: u32) -> SamplerKind {
+        let state = SampleState {
+            seed,
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        };
+        match self {
+            SamplerType::Independent => SamplerKind::Independent(IndependentSampler {
+                seed,
+                rng: SmallRng::seed_from_u64(seed),
+            }),
+            SamplerType::Stratified => SamplerKind::Stratified(StratifiedSampler {
+                state,
+                samples_per_pixel: samples_per_pixel.max(1),
+            }),
+            SamplerType::Halton => SamplerKind::Halton(HaltonSampler { state }),
+            SamplerType::Sobol => SamplerKind::Sobol(SobolSampler { state }),
+        }
+    }
+}
+
+/// Enum-based sampler dispatch, mirroring `MaterialKind`.
+#[derive(Debug, Clone)]
+pub enum SamplerKind {
+    Independent(IndependentSampler),
+    Stratified(StratifiedSampler),
+    Halton(HaltonSampler),
+    Sobol(SobolSampler),
+}
+
+impl Sampler for SamplerKind {
+    fn start_sample(&mut self, pixel: u64, index: u32) {
+        match self {
+            SamplerKind::Independent(s) => s.start_sample(pixel, index),
+            SamplerKind::Stratified(s) => s.start_sample(pixel, index),
+            SamplerKind::Halton(s) => s.start_sample(pixel, index),
+            SamplerKind::Sobol(s) => s.start_sample(pixel, index),
+        }
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        match self {
+            SamplerKind::Independent(s) => s.next_1d(),
+            SamplerKind::Stratified(s) => s.next_1d(),
+            SamplerKind::Halton(s) => s.next_1d(),
+            SamplerKind::Sobol(s) => s.next_1d(),
+        }
+    }
+
+    fn next_2d(&mut self) -> (f64, f64) {
+        match self {
+            SamplerKind::Independent(s) => s.next_2d(),
+            SamplerKind::Stratified(s) => s.next_2d(),
+            SamplerKind::Halton(s) => s.next_2d(),
+            SamplerKind::Sobol(s) => s.next_2d(),
+        }
+    }
+}
+
+/// Uncorrelated uniform random numbers.
+#[derive(Debug, Clone)]
+pub struct IndependentSampler {
+    seed: u64,
+    rng: SmallRng,
+}
+
+impl Sampler for IndependentSampler {
+    fn start_sample(&mut self, pixel: u64, index: u32) {
+        self.rng = SmallRng::seed_from_u64(utils::stream_seed(self.seed, &[pixel, index as u64]));
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        self.rng.random()
+    }
+
+    fn next_2d(&mut self) -> (f64, f64) {
+        (self.rng.random(), self.rng.random())
+    }
+}
+
+/// Position in the sample sequence shared by the deterministic samplers.
+#[derive(Debug, Clone)]
+struct SampleState {
+    seed: u64,
+    pixel: u64,
+    index: u32,
+    dimension: u64,
+}
+
+impl SampleState {
+    fn start(&mut self, pixel: u64, index: u32) {
+        self.pixel = pixel;
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    /// Hash of (seed, pixel, dimension), shared by all samples of a pixel
+    /// so that they can be decorrelated consistently.
+    fn next_dimension_hash(&mut self) -> u64 {
+        let hash = utils::stream_seed(self.seed, &[self.pixel, self.dimension]);
+        self.dimension += 1;
+        hash
+    }
+}
+
+/// Jittered grid: the samples of a pixel each take one randomly chosen
+/// stratum of every dimension (or 2D grid cell), jittered inside it.
+#[derive(Debug, Clone)]
+pub struct StratifiedSampler {
+    state: SampleState,
+    samples_per_pixel: u32,
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_sample(&mut self, pixel: u64, index: u32) {
+        self.state.start(pixel, index);
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let hash = self.state.next_dimension_hash();
+        let count = self.samples_per_pixel;
+        let stratum = permute(self.state.index % count, count, hash as u32);
+        let jitter = hash_to_unit(utils::stream_seed(hash, &[self.state.index as u64]));
+        (stratum as f64 + jitter) / count as f64
+    }
+
+    fn next_2d(&mut self) -> (f64, f64) {
+        let hash = self.state.next_dimension_hash();
+        let nx = (self.samples_per_pixel as f64).sqrt().ceil() as u32;
+        let ny = self.samples_per_pixel.div_ceil(nx);
+        let cell = permute(self.state.index % (nx * ny), nx * ny, hash as u32);
+
+        let jitter = utils::stream_seed(hash, &[self.state.index as u64]);
+        let jx = hash_to_unit(jitter);
+        let jy = hash_to_unit(utils::stream_seed(jitter, &[1]));
+        (
+            ((cell % nx) as f64 + jx) / nx as f64,
+            ((cell / nx) as f64 + jy) / ny as f64,
+        )
+    }
+}
+
+/// Halton sequence with one prime base per dimension, randomized per pixel
+/// with a Cranley-Patterson rotation. Dimensions beyond the prime table
+/// fall back to independent random numbers.
+#[derive(Debug, Clone)]
+pub struct HaltonSampler {
+    state: SampleState,
+}
+
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+impl Sampler for HaltonSampler {
+    fn start_sample(&mut self, pixel: u64, index: u32) {
+        self.state.start(pixel, index);
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let dimension = self.state.dimension as usize;
+        let hash = self.state.next_dimension_hash();
+        let index = self.state.index as u64;
+
+        match PRIMES.get(dimension) {
+            Some(&base) => {
+                let rotated = radical_inverse(base, index) + hash_to_unit(hash);
+                (rotated - rotated.floor()).min(ONE_MINUS_EPSILON)
+            }
+            None => hash_to_unit(utils::stream_seed(hash, &[index])),
+        }
+    }
+
+    fn next_2d(&mut self) -> (f64, f64) {
+        (self.next_1d(), self.next_1d())
+    }
+}
+
+/// Owen-scrambled Sobol points after Burley, "Practical Hash-based Owen
+/// Scrambling" (2020). Each 2D request uses the first two Sobol dimensions
+/// with its own shuffle and scramble seeds, so every dimension pair is
+/// well stratified and independent of the others.
+#[derive(Debug, Clone)]
+pub struct SobolSampler {
+    state: SampleState,
+}
+
+impl Sampler for SobolSampler {
+    fn start_sample(&mut self, pixel: u64, index: u32) {
+        self.state.start(pixel, index);
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let hash = self.state.next_dimension_hash();
+        let index = nested_uniform_scramble(self.state.index, hash as u32);
+        let x = nested_uniform_scramble(index.reverse_bits(), (hash >> 32) as u32);
+        u32_to_unit(x)
+    }
+
+    fn next_2d(&mut self) -> (f64, f64) {
+        let hash = self.state.next_dimension_hash();
+        let index = nested_uniform_scramble(self.state.index, hash as u32);
+        let seeds = utils::stream_seed(hash, &[0]);
+        let x = nested_uniform_scramble(index.reverse_bits(), seeds as u32);
+        let y = nested_uniform_scramble(sobol_dimension_1(index), (seeds >> 32) as u32);
+        (u32_to_unit(x), u32_to_unit(y))
+    }
+}
+
+/// Maps a point in the unit square to the unit disk (Shirley-Chiu concentric
+/// mapping), keeping nearby points nearby so stratification survives.
+pub fn square_to_disk((u, v): (f64, f64)) -> Vec3 {
+    let a = 2.0 * u - 1.0;
+    let b = 2.0 * v - 1.0;
+    if a == 0.0 && b == 0.0 {
+        return Vec3::default();
+    }
+
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+    Vec3::new(r * theta.cos(), r * theta.sin(), 0.0)
+}
+
+/// Maps a point in the unit square to a uniformly distributed unit vector.
+pub fn square_to_sphere((u, v): (f64, f64)) -> Vec3 {
+    let z = 1.0 - 2.0 * u;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * v;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
+
+fn hash_to_unit(hash: u64) -> f64 {
+    (hash >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+fn u32_to_unit(x: u32) -> f64 {
+    x as f64 / 4_294_967_296.0
+}
+
+fn radical_inverse(base: u64, mut index: u64) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut reversed = 0.0;
+    let mut scale = inv_base;
+    while index > 0 {
+        reversed += (index % base) as f64 * scale;
+        scale *= inv_base;
+        index /= base;
+    }
+    reversed.min(ONE_MINUS_EPSILON)
+}
+
+/// Second Sobol dimension (the first is the bit-reversed index).
+fn sobol_dimension_1(index: u32) -> u32 {
+    let mut result = 0;
+    let mut direction = 1u32 << 31;
+    let mut bits = index;
+    while bits != 0 {
+        if bits & 1 == 1 {
+            result ^= direction;
+        }
+        direction ^= direction >> 1;
+        bits >>= 1;
+    }
+    result
+}
+
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x
+}
+
+/// Owen scrambling of the bits of `x`, read from the most significant end.
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+/// Kensler's hash-based permutation of `0..len`, from "Correlated
+/// Multi-Jittered Sampling" (2013).
+fn permute(mut i: u32, len: u32, p: u32) -> u32 {
+    let mut w = len - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < len {
+            break;
+        }
+    }
+    (i.wrapping_add(p)) % len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [SamplerType; 4] = [
+        SamplerType::Independent,
+        SamplerType::Stratified,
+        SamplerType::Halton,
+        SamplerType::Sobol,
+    ];
+
+    /// Draws `dims` 2D points for each of `spp` samples of one pixel.
+    fn points(kind: SamplerType, spp: u32, pixel: u64, dims: usize) -> Vec<Vec<(f64, f64)>> {
+        let mut sampler = kind.build(7, spp);
+        (0..spp)
+            .map(|i| {
+                sampler.start_sample(pixel, i);
+                (0..dims).map(|_| sampler.next_2d()).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_samples_are_in_unit_square_and_repeatable() {
+        for kind in ALL {
+            let first = points(kind, 16, 3, 20);
+            assert_eq!(first, points(kind, 16, 3, 20), "{kind:?}");
+            assert_ne!(first, points(kind, 16, 4, 20), "{kind:?}");
+            for (x, y) in first.iter().flatten() {
+                assert!((0.0..1.0).contains(x) && (0.0..1.0).contains(y), "{kind:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid_cells_are_covered_once() {
+        // With 16 samples, stratified and Sobol points land exactly once in
+        // every cell of a 4x4 grid, in every dimension pair.
+        for kind in [SamplerType::Stratified, SamplerType::Sobol] {
+            for pixel in 0..4 {
+                let samples = points(kind, 16, pixel, 6);
+                for dim in 0..6 {
+                    let mut cells = [0; 16];
+                    for sample in &samples {
+                        let (x, y) = sample[dim];
+                        cells[(x * 4.0) as usize + 4 * (y * 4.0) as usize] += 1;
+                    }
+                    assert_eq!(cells, [1; 16], "{kind:?} dimension {dim}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_1d_strata_are_covered_once() {
+        for kind in [SamplerType::Stratified, SamplerType::Sobol] {
+            let mut sampler = kind.build(11, 8);
+            let mut strata = [0; 8];
+            for i in 0..8 {
+                sampler.start_sample(5, i);
+                sampler.next_2d();
+                strata[(sampler.next_1d() * 8.0) as usize] += 1;
+            }
+            assert_eq!(strata, [1; 8], "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn test_low_discrepancy_beats_random() {
+        // Estimate the area of a quarter disk over many pixels and compare
+        // the average error of each sampler.
+        let error = |kind: SamplerType| {
+            let mut total = 0.0;
+            for pixel in 0..200 {
+                let samples = points(kind, 16, pixel, 1);
+                let inside = samples
+                    .iter()
+                    .filter(|s| s[0].0 * s[0].0 + s[0].1 * s[0].1 < 1.0)
+                    .count();
+                total += (inside as f64 / 16.0 - PI / 4.0).abs();
+            }
+            total / 200.0
+        };
+
+        let random = error(SamplerType::Independent);
+        for kind in [
+            SamplerType::Stratified,
+            SamplerType::Halton,
+            SamplerType::Sobol,
+        ] {
+            assert!(error(kind) < 0.7 * random, "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn test_permute_is_a_permutation() {
+        for len in [1, 2, 7, 16, 100] {
+            let mut seen = vec![false; len as usize];
+            for i in 0..len {
+                seen[permute(i, len, 0x1234_5678) as usize] = true;
+            }
+            assert!(seen.iter().all(|&s| s), "len {len}");
+        }
+    }
+
+    #[test]
+    fn test_square_to_disk_and_sphere() {
+        for &(u, v) in &[(0.0, 0.0), (0.5, 0.5), (0.99, 0.01), (0.3, 0.8)] {
+            assert!(square_to_disk((u, v)).length() <= 1.0 + 1e-12);
+            assert!((square_to_sphere((u, v)).length() - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_parse_sampler() {
+        assert_eq!("Sobol".parse(), Ok(SamplerType::Sobol));
+        assert_eq!("jittered".parse(), Ok(SamplerType::Stratified));
+        assert!("latin".parse::<SamplerType>().is_err());
+    }
+}
@@ -0,0 +1,232 @@
+use std::{path::Path, sync::Arc};
+
+use crate::{
+    imaging::color::srgb_to_linear,
+    math::{
+        interval::Interval,
+        perlin::Perlin,
+        vec3::{Color, Point3},
+    },
+};
+
+/// Enum-based texture dispatch, mirroring `MaterialKind`.
+#[derive(Debug, Clone)]
+pub enum TextureKind {
+    Solid(SolidColor),
+    Checker(CheckerTexture),
+    Image(ImageTexture),
+    Noise(NoiseTexture),
+}
+
+impl TextureKind {
+    pub fn value(&self, u: f64, v: f64, p: Point3) -> Color {
+        match self {
+            TextureKind::Solid(t) => t.value(u, v, p),
+            TextureKind::Checker(t) => t.value(u, v, p),
+            TextureKind::Image(t) => t.value(u, v, p),
+            TextureKind::Noise(t) => t.value(u, v, p),
+        }
+    }
+}
+
+impl From<Color> for TextureKind {
+    fn from(color: Color) -> Self {
+        TextureKind::Solid(SolidColor::new(color))
+    }
+}
+
+/// Trait for textures that can be sampled at a surface point.
+pub trait Texture {
+    /// Returns the color at texture coordinates `(u, v)` and world point `p`.
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color;
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A constant color.
+pub struct SolidColor {
+    pub albedo: Color,
+}
+
+impl SolidColor {
+    pub fn new(albedo: Color) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: Point3) -> Color {
+        self.albedo
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A 3D checker pattern alternating between two textures in space.
+pub struct CheckerTexture {
+    inv_scale: f64,
+    even: Box<TextureKind>,
+    odd: Box<TextureKind>,
+}
+
+impl CheckerTexture {
+    /// Creates a checker whose cells are `scale` units wide.
+    pub fn new(scale: f64, even: TextureKind, odd: TextureKind) -> Self {
+        Self {
+            inv_scale: 1.0 / scale,
+            even: Box::new(even),
+            odd: Box::new(odd),
+        }
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color {
+        let x = (self.inv_scale * p.x).floor() as i64;
+        let y = (self.inv_scale * p.y).floor() as i64;
+        let z = (self.inv_scale * p.z).floor() as i64;
+
+        if (x + y + z) % 2 == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A texture backed by an image file, sampled with the hit's UVs.
+pub struct ImageTexture {
+    width: u32,
+    height: u32,
+    pixels: Arc<Vec<Color>>,
+}
+
+impl ImageTexture {
+    /// Loads an image and converts its sRGB-encoded pixels to linear colors.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, image::ImageError> {
+        let img = image::open(path)?.to_rgb8();
+        let (width, height) = img.dimensions();
+
+        let pixels = img
+            .pixels()
+            .map(|px| {
+                let decode = |c: u8| srgb_to_linear(c as f64 / 255.0);
+                Color::new(decode(px[0]), decode(px[1]), decode(px[2]))
+            })
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            pixels: Arc::new(pixels),
+        })
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: Point3) -> Color {
+        if self.width == 0 || self.height == 0 {
+            return Color::new(0.0, 1.0, 1.0);
+        }
+
+        // Image rows go top to bottom, while v goes bottom to top.
+        let unit = Interval::new(0.0, 1.0);
+        let u = unit.clamp(u);
+        let v = 1.0 - unit.clamp(v);
+
+        let i = ((u * self.width as f64) as u32).min(self.width - 1);
+        let j = ((v * self.height as f64) as u32).min(self.height - 1);
+
+        self.pixels[(j * self.width + i) as usize]
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A marble-like pattern driven by Perlin turbulence.
+pub struct NoiseTexture {
+    noise: Arc<Perlin>,
+    scale: f64,
+    color: Color,
+}
+
+impl NoiseTexture {
+    pub fn new(scale: f64, color: Color) -> Self {
+        Self {
+            noise: Arc::new(Perlin::new()),
+            scale,
+            color,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, p: Point3) -> Color {
+        let phase = self.scale * p.z + 10.0 * self.noise.turbulence(p, 7);
+        self.color * 0.5 * (1.0 + phase.sin())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::utils;
+
+    #[test]
+    fn test_checker_cells_alternate() {
+        let (white, black) = (Color::new(1.0, 1.0, 1.0), Color::default());
+        let checker = CheckerTexture::new(0.5, white.into(), black.into());
+        let at = |x, y, z| checker.value(0.0, 0.0, Point3::new(x, y, z));
+
+        assert_eq!(at(0.25, 0.25, 0.25), white);
+        // One cell over along any axis flips the color, two flips it back.
+        assert_eq!(at(0.75, 0.25, 0.25), black);
+        assert_eq!(at(0.25, 0.75, 0.25), black);
+        assert_eq!(at(0.25, 0.25, -0.25), black);
+        assert_eq!(at(0.75, 0.75, 0.25), white);
+        assert_eq!(at(-0.25, -0.25, 0.25), white);
+    }
+
+    #[test]
+    fn test_image_lookup_flips_v_and_clamps() {
+        // Red, green on the top row; blue, white on the bottom one.
+        let mut img = image::RgbImage::new(2, 2);
+        img.put_pixel(0, 0, image::Rgb([255, 0, 0]));
+        img.put_pixel(1, 0, image::Rgb([0, 255, 0]));
+        img.put_pixel(0, 1, image::Rgb([0, 0, 255]));
+        img.put_pixel(1, 1, image::Rgb([255, 255, 255]));
+        let path = std::env::temp_dir().join(format!("rust-rt-texture-{}.png", std::process::id()));
+        img.save(&path).unwrap();
+        let texture = ImageTexture::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let texture = texture.unwrap();
+
+        let at = |u, v| texture.value(u, v, Point3::default());
+        assert_eq!(at(0.25, 0.75), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(at(0.75, 0.75), Color::new(0.0, 1.0, 0.0));
+        assert_eq!(at(0.25, 0.25), Color::new(0.0, 0.0, 1.0));
+        assert_eq!(at(0.75, 0.25), Color::new(1.0, 1.0, 1.0));
+        // The edges and anything past them clamp to the border texels.
+        assert_eq!(at(1.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(at(-3.0, 7.0), Color::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_missing_image_fails_to_load() {
+        let path = std::env::temp_dir().join("rust-rt-texture-missing.png");
+        assert!(ImageTexture::load(path).is_err());
+    }
+
+    #[test]
+    fn test_noise_is_deterministic_under_a_seed() {
+        let noise = || {
+            utils::reseed(12);
+            NoiseTexture::new(4.0, Color::new(1.0, 1.0, 1.0))
+        };
+        let (a, b) = (noise(), noise());
+        for i in 0..20 {
+            let p = Point3::new(i as f64 * 0.37, 1.0 - i as f64 * 0.11, i as f64 * 0.23);
+            let value = a.value(0.0, 0.0, p);
+            assert_eq!(value, b.value(0.0, 0.0, p));
+            assert!((0.0..=1.0).contains(&value.x));
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::math::vec3::Color;
+
+/// Curve compressing linear radiance into the displayable [0, 1] range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum ToneMapOperator {
+    /// Leaves colors untouched; anything above 1 is clipped on output.
+    #[default]
+    Clamp,
+    /// `c / (1 + c)`: never clips, but greys out highlights.
+    Reinhard,
+    /// Reinhard rescaled so that the white point maps exactly to 1.
+    ReinhardExtended,
+    /// Narkowicz's fit of the ACES filmic reference curve.
+    Aces,
+    /// John Hable's filmic curve from Uncharted 2.
+    Uncharted,
+}
+
+impl FromStr for ToneMapOperator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "clamp" | "none" => Ok(ToneMapOperator::Clamp),
+            "reinhard" => Ok(ToneMapOperator::Reinhard),
+            "reinhard-extended" => Ok(ToneMapOperator::ReinhardExtended),
+            "aces" => Ok(ToneMapOperator::Aces),
+            "uncharted" | "hable" => Ok(ToneMapOperator::Uncharted),
+            _ => Err(format!(
+                "unknown tone mapping operator '{s}' (expected 'clamp', 'reinhard', \
+                 'reinhard-extended', 'aces' or 'uncharted')"
+            )),
+        }
+    }
+}
+
+/// Exposure adjustment followed by a tone curve, turning scene radiance
+/// into display-referred linear colors ready for sRGB encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ToneMap {
+    pub operator: ToneMapOperator,
+    /// Exposure in stops: every +1 doubles the brightness.
+    pub exposure: f64,
+    /// Input level that maps to white, for the operators that use one.
+    /// Defaults to 4 for extended Reinhard and 5.6 for Uncharted.
+    pub white_point: Option<f64>,
+}
+
+impl ToneMap {
+    pub fn new(operator: ToneMapOperator) -> Self {
+        Self {
+            operator,
+            ..Default::default()
+        }
+    }
+
+    pub fn apply(&self, color: Color) -> Color {
+        let scale = self.exposure.exp2();
+        let c = color * scale;
+
+        match self.operator {
+            ToneMapOperator::Clamp => c,
+            ToneMapOperator::Reinhard => map_channels(c, |x| x / (1.0 + x)),
+            ToneMapOperator::ReinhardExtended => {
+                let white = self.white_point.unwrap_or(4.0);
+                let white_squared = white * white;
+                map_channels(c, |x| x * (1.0 + x / white_squared) / (1.0 + x))
+            }
+            ToneMapOperator::Aces => map_channels(c, |x| {
+                (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+            }),
+            ToneMapOperator::Uncharted => {
+                const EXPOSURE_BIAS: f64 = 2.0;
+                let white = self.white_point.unwrap_or(5.6);
+                let white_scale = 1.0 / hable_partial(white * EXPOSURE_BIAS);
+                map_channels(c, |x| hable_partial(x * EXPOSURE_BIAS) * white_scale)
+            }
+        }
+    }
+}
+
+fn map_channels(c: Color, f: impl Fn(f64) -> f64) -> Color {
+    let f = |x: f64| f(x.max(0.0));
+    Color::new(f(c.x), f(c.y), f(c.z))
+}
+
+fn hable_partial(x: f64) -> f64 {
+    const A: f64 = 0.15; // shoulder strength
+    const B: f64 = 0.50; // linear strength
+    const C: f64 = 0.10; // linear angle
+    const D: f64 = 0.20; // toe strength
+    const E: f64 = 0.02; // toe numerator
+    const F: f64 = 0.30; // toe denominator
+
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(v: f64) -> Color {
+        Color::new(v, v, v)
+    }
+
+    #[test]
+    fn test_exposure_in_stops() {
+        let tonemap = ToneMap {
+            exposure: 2.0,
+            ..Default::default()
+        };
+        assert_eq!(tonemap.apply(gray(0.1)), gray(0.4));
+    }
+
+    #[test]
+    fn test_operators_are_monotonic_and_bounded() {
+        for operator in [
+            ToneMapOperator::Reinhard,
+            ToneMapOperator::ReinhardExtended,
+            ToneMapOperator::Aces,
+            ToneMapOperator::Uncharted,
+        ] {
+            let tonemap = ToneMap::new(operator);
+            assert!(tonemap.apply(gray(0.0)).x.abs() < 1e-9, "{operator:?}");
+
+            let mut previous = 0.0;
+            for i in 1..200 {
+                let y = tonemap.apply(gray(i as f64 * 0.05)).x;
+                assert!(y > previous, "{operator:?} is not increasing at {i}");
+                previous = y;
+            }
+            assert!(tonemap.apply(gray(4.0)).x <= 1.0 + 1e-9, "{operator:?}");
+        }
+    }
+
+    #[test]
+    fn test_white_point_maps_to_one() {
+        let reinhard = ToneMap {
+            white_point: Some(3.0),
+            ..ToneMap::new(ToneMapOperator::ReinhardExtended)
+        };
+        assert!((reinhard.apply(gray(3.0)).x - 1.0).abs() < 1e-9);
+
+        let uncharted = ToneMap::new(ToneMapOperator::Uncharted);
+        assert!((uncharted.apply(gray(5.6)).x - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_operator() {
+        assert_eq!("ACES".parse(), Ok(ToneMapOperator::Aces));
+        assert_eq!(
+            "reinhard-extended".parse(),
+            Ok(ToneMapOperator::ReinhardExtended)
+        );
+        assert!("filmic".parse::<ToneMapOperator>().is_err());
+    }
+}
@@ -1,18 +1,33 @@
 use std::fs::File;
 use std::io::BufWriter;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::Duration;
 
-use clap::Parser;
-use rust_rt::{engine::Engine, scene};
+use clap::{ArgGroup, Parser};
+use rust_rt::{
+    engine::{Engine, Progressive, Scene},
+    imaging::{
+        AdaptiveSampling, Aov, Canvas, Denoiser, Filter, FilterType, Integrator, SamplerType,
+        ToneMap, ToneMapOperator,
+    },
+    math::utils,
+    scene,
+};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(group(ArgGroup::new("limit").args(["time_limit", "max_samples"]).multiple(true)))]
 struct Args {
     /// Width of the output image
     #[arg(short = 'w', long, default_value_t = 1200)]
     width: u32,
 
-    /// Number of random samples per pixel
+    /// Number of random samples per pixel (the maximum with --adaptive-threshold)
     #[arg(short = 's', long, default_value_t = 100)]
     samples: u32,
 
@@ -20,41 +35,252 @@ struct Args {
     #[arg(short = 'd', long, default_value_t = 50)]
     depth: u32,
 
+    /// Bounces after which paths may be ended by Russian roulette
+    #[arg(long, default_value_t = 3)]
+    rr_depth: u32,
+
+    /// Light transport algorithm: 'nee' (direct light sampling with MIS) or 'naive'
+    #[arg(long, default_value = "nee")]
+    integrator: Integrator,
+
+    /// Enables adaptive sampling: a pixel stops once the standard error of
+    /// its mean luminance is below this fraction of the mean (e.g. 0.02).
+    #[arg(long, value_parser = parse_positive)]
+    adaptive_threshold: Option<f64>,
+
+    /// Samples every pixel takes before adaptive sampling may stop it
+    #[arg(long, default_value_t = 16)]
+    min_samples: u32,
+
+    /// Comma-separated AOVs to write next to the output, named with a
+    /// suffix (e.g. image_depth.png): depth, normal, albedo, material-id,
+    /// object-id, direct, indirect
+    #[arg(long, value_delimiter = ',')]
+    aov: Vec<Aov>,
+
+    /// Denoises the output, guided by normals and albedo. The raw render
+    /// is kept with a _noisy suffix.
+    #[arg(long)]
+    denoise: bool,
+
+    /// Writes an image of the number of samples taken per pixel
+    #[arg(long)]
+    sample_heatmap: Option<PathBuf>,
+
+    /// Renders progressively, stopping after this many seconds
+    #[arg(long, value_parser = parse_seconds)]
+    time_limit: Option<Duration>,
+
+    /// Renders progressively, stopping after this many samples per pixel
+    #[arg(long)]
+    max_samples: Option<u32>,
+
+    /// Samples per pixel added by each progressive pass
+    #[arg(long, default_value_t = 16)]
+    pass_samples: u32,
+
+    /// Overwrites the output with the image so far after each progressive
+    /// pass. Needs --time-limit or --max-samples.
+    #[arg(long, requires = "limit")]
+    save_passes: bool,
+
+    /// Sample generator: 'independent', 'stratified', 'halton' or 'sobol'.
+    /// Overrides the scene file.
+    #[arg(long)]
+    sampler: Option<SamplerType>,
+
+    /// Pixel reconstruction filter: 'box', 'tent', 'gaussian', 'mitchell' or
+    /// 'lanczos'. Overrides the scene file.
+    #[arg(long)]
+    filter: Option<FilterType>,
+
+    /// Radius of the reconstruction filter in pixels (at least 0.5).
+    /// Overrides the scene file.
+    #[arg(long, value_parser = parse_filter_radius)]
+    filter_radius: Option<f64>,
+
+    /// Tone mapping for PNG/PPM output: 'clamp', 'reinhard', 'reinhard-extended',
+    /// 'aces' or 'uncharted'. Overrides the scene file.
+    #[arg(long)]
+    tonemap: Option<ToneMapOperator>,
+
+    /// Exposure adjustment in stops. Overrides the scene file.
+    #[arg(long, allow_negative_numbers = true, value_parser = parse_finite)]
+    exposure: Option<f64>,
+
+    /// Input level mapped to white by 'reinhard-extended' and 'uncharted'.
+    /// Overrides the scene file.
+    #[arg(long, value_parser = parse_positive)]
+    white_point: Option<f64>,
+
+    /// Seed for all random numbers. Runs with the same seed and options
+    /// produce identical images. Picked at random if omitted.
+    #[arg(long)]
+    seed: Option<u64>,
+
     /// Path to a scene YAML file. If omitted, generates a random scene.
     #[arg(long)]
     scene: Option<String>,
 
-    /// Output filename
+    /// Output filename (.png, .ppm, or .exr/.hdr for linear HDR data)
     #[arg(short = 'o', long, default_value = "renders/image.png")]
     output: String,
 }
 
 fn main() {
     let args = Args::parse();
-    let engine = Engine::new(args.samples, args.depth);
+    let seed = args.seed.unwrap_or_else(utils::random_seed);
+    println!("Using seed {seed}");
+    let mut engine = Engine::new(args.samples, args.depth)
+        .with_integrator(args.integrator)
+        .with_roulette_depth(args.rr_depth)
+        .with_seed(seed);
+    if args.time_limit.is_some() || args.max_samples.is_some() {
+        engine = engine.with_progressive(Progressive {
+            pass_samples: args.pass_samples.max(1),
+            max_samples: args.max_samples,
+            time_limit: args.time_limit,
+        });
+    }
+    if !args.aov.is_empty() {
+        engine = engine.with_aovs(args.aov.clone());
+    }
+    if args.denoise {
+        engine = engine.with_denoiser(Denoiser::default());
+    }
+    if let Some(threshold) = args.adaptive_threshold {
+        engine = engine.with_adaptive_sampling(AdaptiveSampling {
+            min_samples: args.min_samples,
+            threshold,
+        });
+    }
 
-    let (world, camera, width, height, background) = if let Some(ref path) = args.scene {
+    let mut scene = if let Some(ref path) = args.scene {
         println!("Loading scene from {}...", path);
-        engine.load_scene_from_file(path, args.width)
+        match engine.load_scene_from_file(path, args.width) {
+            Ok(scene) => scene,
+            Err(err) => {
+                eprintln!("error: {err}");
+                process::exit(1);
+            }
+        }
     } else {
         println!("No scene file provided. Generating random book scene...");
         let aspect_ratio = 16.0 / 9.0;
-        let (world, camera) = scene::random_book_scene(aspect_ratio);
+        let (world, camera) = scene::random_book_scene(aspect_ratio, seed);
         let height = (args.width as f64 / aspect_ratio) as u32;
         let default_bg = rust_rt::scene::Background::VerticalGradient {
             top: rust_rt::math::vec3::Color::new(0.5, 0.7, 1.0),
             bottom: rust_rt::math::vec3::Color::new(1.0, 1.0, 1.0),
         };
-        (world, camera, args.width, height, default_bg)
+        Scene {
+            world,
+            camera,
+            width: args.width,
+            height,
+            background: default_bg,
+            tonemap: ToneMap::default(),
+            sampler: SamplerType::default(),
+            filter: Filter::default(),
+            atmosphere: None,
+        }
     };
 
-    let canvas = engine.render(&world, &camera, width, height, &background);
+    if let Some(sampler) = args.sampler {
+        scene.sampler = sampler;
+    }
+    if let Some(kind) = args.filter {
+        scene.filter = Filter::new(kind);
+    }
+    if let Some(radius) = args.filter_radius {
+        scene.filter.radius = radius;
+    }
+    if let Some(operator) = args.tonemap {
+        scene.tonemap.operator = operator;
+    }
+    if let Some(exposure) = args.exposure {
+        scene.tonemap.exposure = exposure;
+    }
+    if let Some(white_point) = args.white_point {
+        scene.tonemap.white_point = Some(white_point);
+    }
+
+    // The first Ctrl-C finishes with the samples taken so far; a second
+    // one exits immediately.
+    let interrupt = Arc::new(AtomicBool::new(false));
+    let handler_flag = Arc::clone(&interrupt);
+    ctrlc::set_handler(move || {
+        if handler_flag.swap(true, Ordering::Relaxed) {
+            process::exit(130);
+        }
+        eprintln!("\nInterrupted; finishing the rows in progress (Ctrl-C again to abort)...");
+    })
+    .expect("Failed to install Ctrl-C handler");
+    let engine = engine.with_interrupt(interrupt);
 
     let mut path = PathBuf::from(&args.output);
     if path.extension().is_none() {
         path.set_extension("ppm");
     }
 
+    let output = engine.render_with_progress(&scene, |output| {
+        if args.save_passes {
+            save_image(&output.canvas, &path, &scene.tonemap);
+        }
+    });
+    match &output.denoised {
+        Some(denoised) => {
+            save_image(denoised, &path, &scene.tonemap);
+            save_image(
+                &output.canvas,
+                &suffixed_path(&path, "noisy"),
+                &scene.tonemap,
+            );
+        }
+        None => save_image(&output.canvas, &path, &scene.tonemap),
+    }
+
+    for (aov, canvas) in &output.aovs {
+        let aov_path = suffixed_path(&path, aov.suffix());
+        let tonemap = if aov.is_lighting() {
+            scene.tonemap
+        } else {
+            ToneMap::default()
+        };
+        if is_8bit(&aov_path) {
+            save_image(&aov.for_8bit_output(canvas), &aov_path, &tonemap);
+        } else {
+            save_image(canvas, &aov_path, &tonemap);
+        }
+    }
+
+    if let Some(path) = &args.sample_heatmap {
+        let max = output.sample_counts.iter().copied().max().unwrap_or(0);
+        let heatmap = Canvas::heatmap(scene.width, scene.height, &output.sample_counts, max);
+        save_image(&heatmap, path, &ToneMap::default());
+    }
+
+    println!("Done!");
+}
+
+/// Inserts `_suffix` before the extension: `image.png` -> `image_depth.png`.
+fn suffixed_path(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name = format!("{stem}_{suffix}");
+    if let Some(extension) = path.extension() {
+        name = format!("{name}.{}", extension.to_string_lossy());
+    }
+    path.with_file_name(name)
+}
+
+/// Whether `path` names a tone mapped, 8-bit format rather than EXR or HDR.
+fn is_8bit(path: &Path) -> bool {
+    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("png");
+    !matches!(extension.to_lowercase().as_str(), "exr" | "hdr")
+}
+
+/// Saves a canvas in the format given by the file extension.
+fn save_image(canvas: &Canvas, path: &Path, tonemap: &ToneMap) {
     println!("Saving to {}...", path.display());
 
     let extension = path
@@ -65,18 +291,56 @@ fn main() {
 
     match extension.as_str() {
         "png" => {
-            canvas.save_png(&path).expect("Failed to save PNG");
+            canvas.save_png(path, tonemap).expect("Failed to save PNG");
+        }
+        "exr" => {
+            canvas.save_exr(path).expect("Failed to save EXR");
+        }
+        "hdr" => {
+            canvas.save_hdr(path).expect("Failed to save HDR");
         }
         "ppm" => {
-            let file = File::create(&path).expect("Failed to create file");
+            let file = File::create(path).expect("Failed to create file");
             let mut writer = BufWriter::new(file);
-            canvas.write_ppm(&mut writer).expect("Failed to save PPM");
+            canvas
+                .write_ppm(&mut writer, tonemap)
+                .expect("Failed to save PPM");
         }
         _ => {
             eprintln!("Unknown format '{}'. Defaulting to PNG.", extension);
-            canvas.save_png(&path).expect("Failed to save PNG");
+            canvas.save_png(path, tonemap).expect("Failed to save PNG");
         }
     }
+}
 
-    println!("Done!");
+fn parse_seconds(s: &str) -> Result<Duration, String> {
+    let seconds: f64 = s.parse().map_err(|err| format!("{err}"))?;
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("invalid duration '{s}'"))
+}
+
+fn parse_filter_radius(s: &str) -> Result<f64, String> {
+    let radius: f64 = s.parse().map_err(|err| format!("{err}"))?;
+    if radius >= Filter::MIN_RADIUS && radius.is_finite() {
+        Ok(radius)
+    } else {
+        Err(format!("must be at least {}", Filter::MIN_RADIUS))
+    }
+}
+
+fn parse_finite(s: &str) -> Result<f64, String> {
+    let value: f64 = s.parse().map_err(|err| format!("{err}"))?;
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err("must be a finite number".to_string())
+    }
+}
+
+fn parse_positive(s: &str) -> Result<f64, String> {
+    let value: f64 = s.parse().map_err(|err| format!("{err}"))?;
+    if value > 0.0 && value.is_finite() {
+        Ok(value)
+    } else {
+        Err("must be a positive number".to_string())
+    }
 }
@@ -0,0 +1,252 @@
+use crate::math::{
+    interval::Interval,
+    ray::Ray,
+    vec3::{Point3, Vec3},
+};
+
+/// An axis-aligned bounding box, stored as one interval per axis.
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Aabb::EMPTY
+    }
+}
+
+impl Aabb {
+    pub const EMPTY: Aabb = Aabb {
+        x: Interval::EMPTY,
+        y: Interval::EMPTY,
+        z: Interval::EMPTY,
+    };
+    pub const UNIVERSE: Aabb = Aabb {
+        x: Interval::UNIVERSE,
+        y: Interval::UNIVERSE,
+        z: Interval::UNIVERSE,
+    };
+
+    /// Creates a box from per-axis intervals, padding degenerate axes so that
+    /// flat primitives (e.g. axis-aligned triangles) still have a volume.
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        let mut bbox = Aabb { x, y, z };
+        bbox.pad_to_minimums();
+        bbox
+    }
+
+    /// Creates the box spanned by two opposite corners, in any order.
+    pub fn from_points(a: Point3, b: Point3) -> Self {
+        Aabb::new(
+            Interval::new(a.x.min(b.x), a.x.max(b.x)),
+            Interval::new(a.y.min(b.y), a.y.max(b.y)),
+            Interval::new(a.z.min(b.z), a.z.max(b.z)),
+        )
+    }
+
+    /// Returns the smallest box enclosing both `a` and `b`.
+    pub fn surrounding(a: Aabb, b: Aabb) -> Self {
+        Aabb {
+            x: Interval::merge(a.x, b.x),
+            y: Interval::merge(a.y, b.y),
+            z: Interval::merge(a.z, b.z),
+        }
+    }
+
+    pub fn axis_interval(&self, axis: usize) -> Interval {
+        match axis {
+            1 => self.y,
+            2 => self.z,
+            _ => self.x,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.min > self.x.max || self.y.min > self.y.max || self.z.min > self.z.max
+    }
+
+    /// Returns false if the box reaches infinity along any axis, as it does
+    /// for infinite planes.
+    pub fn is_bounded(&self) -> bool {
+        [self.x, self.y, self.z]
+            .iter()
+            .all(|i| i.min.is_finite() && i.max.is_finite())
+    }
+
+    /// Extent of the box along each axis.
+    pub fn size(&self) -> Vec3 {
+        Vec3::new(self.x.size(), self.y.size(), self.z.size())
+    }
+
+    pub fn contains(&self, p: Point3) -> bool {
+        self.x.contains(p.x) && self.y.contains(p.y) && self.z.contains(p.z)
+    }
+
+    /// Returns true if the two boxes share at least one point.
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.x.min <= other.x.max
+            && other.x.min <= self.x.max
+            && self.y.min <= other.y.max
+            && other.y.min <= self.y.max
+            && self.z.min <= other.z.max
+            && other.z.min <= self.z.max
+    }
+
+    /// Index (0 = x, 1 = y, 2 = z) of the axis with the largest extent.
+    pub fn longest_axis(&self) -> usize {
+        if self.x.size() > self.y.size() {
+            if self.x.size() > self.z.size() { 0 } else { 2 }
+        } else if self.y.size() > self.z.size() {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        Vec3::new(
+            0.5 * (self.x.min + self.x.max),
+            0.5 * (self.y.min + self.y.max),
+            0.5 * (self.z.min + self.z.max),
+        )
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let (dx, dy, dz) = (self.x.size(), self.y.size(), self.z.size());
+        2.0 * (dx * dy + dy * dz + dz * dx)
+    }
+
+    /// Slab test: returns true if the ray enters the box within `ray_t`.
+    pub fn hit(&self, ray: &Ray, ray_t: Interval) -> bool {
+        self.clip(ray, ray_t).is_some()
+    }
+
+    /// Slab test: the part of `ray_t` during which the ray is inside the
+    /// box, or `None` if it misses the box within `ray_t`.
+    pub fn clip(&self, ray: &Ray, mut ray_t: Interval) -> Option<Interval> {
+        let origin = [ray.origin.x, ray.origin.y, ray.origin.z];
+        let direction = [ray.direction.x, ray.direction.y, ray.direction.z];
+
+        for axis in 0..3 {
+            let slab = self.axis_interval(axis);
+            let inv_d = 1.0 / direction[axis];
+
+            let t0 = (slab.min - origin[axis]) * inv_d;
+            let t1 = (slab.max - origin[axis]) * inv_d;
+            let (t_near, t_far) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+
+            if t_near > ray_t.min {
+                ray_t.min = t_near;
+            }
+            if t_far < ray_t.max {
+                ray_t.max = t_far;
+            }
+
+            if ray_t.max <= ray_t.min {
+                return None;
+            }
+        }
+
+        Some(ray_t)
+    }
+
+    fn pad_to_minimums(&mut self) {
+        const DELTA: f64 = 0.0001;
+        if self.x.size() < DELTA {
+            self.x = self.x.expand(DELTA);
+        }
+        if self.y.size() < DELTA {
+            self.y = self.y.expand(DELTA);
+        }
+        if self.z.size() < DELTA {
+            self.z = self.z.expand(DELTA);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> Aabb {
+        Aabb::from_points(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn test_hit_through_center() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(unit_box().hit(&ray, Interval::new(0.0, f64::INFINITY)));
+    }
+
+    #[test]
+    fn test_miss_beside_box() {
+        let ray = Ray::new(Vec3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(!unit_box().hit(&ray, Interval::new(0.0, f64::INFINITY)));
+    }
+
+    #[test]
+    fn test_hit_respects_interval() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        // The box spans t in [4, 6].
+        assert!(!unit_box().hit(&ray, Interval::new(0.0, 3.5)));
+        assert!(!unit_box().hit(&ray, Interval::new(6.5, 10.0)));
+        assert!(unit_box().hit(&ray, Interval::new(5.5, 10.0)));
+    }
+
+    #[test]
+    fn test_clip_to_the_box() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -2.0));
+        let span = |bbox: Aabb, min, max| {
+            bbox.clip(&ray, Interval::new(min, max))
+                .map(|t| (t.min, t.max))
+        };
+        assert_eq!(span(unit_box(), 0.0, f64::INFINITY), Some((2.0, 3.0)));
+        assert_eq!(span(unit_box(), 2.5, 10.0), Some((2.5, 3.0)));
+        assert_eq!(span(unit_box(), 0.0, 1.5), None);
+        assert_eq!(
+            span(Aabb::UNIVERSE, 0.0, f64::INFINITY),
+            Some((0.0, f64::INFINITY))
+        );
+    }
+
+    #[test]
+    fn test_hit_from_inside() {
+        let ray = Ray::new(Vec3::default(), Vec3::new(1.0, 2.0, 3.0));
+        assert!(unit_box().hit(&ray, Interval::new(0.0, f64::INFINITY)));
+    }
+
+    #[test]
+    fn test_flat_box_is_padded() {
+        let flat = Aabb::from_points(Vec3::new(-1.0, -1.0, 0.0), Vec3::new(1.0, 1.0, 0.0));
+        assert!(flat.z.size() > 0.0);
+
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(flat.hit(&ray, Interval::new(0.0, f64::INFINITY)));
+    }
+
+    #[test]
+    fn test_surrounding_and_empty() {
+        let a = Aabb::from_points(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0));
+        let b = Aabb::from_points(Vec3::new(2.0, -1.0, 0.5), Vec3::new(3.0, 0.5, 4.0));
+
+        let merged = Aabb::surrounding(Aabb::EMPTY, Aabb::surrounding(a, b));
+        assert_eq!((merged.x.min, merged.x.max), (0.0, 3.0));
+        assert_eq!((merged.y.min, merged.y.max), (-1.0, 1.0));
+        assert_eq!((merged.z.min, merged.z.max), (0.0, 4.0));
+        assert_eq!(merged.longest_axis(), 2);
+
+        assert!(Aabb::EMPTY.is_empty());
+        assert!(merged.is_bounded());
+        assert!(!Aabb::surrounding(a, Aabb::UNIVERSE).is_bounded());
+        assert_eq!(Aabb::EMPTY.surface_area(), 0.0);
+        assert!(!a.overlaps(&b));
+        assert!(merged.overlaps(&a));
+        assert!(merged.contains(b.centroid()));
+    }
+}
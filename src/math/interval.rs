@@ -1,5 +1,3 @@
-#![allow(dead_code)]
-
 #[derive(Debug, Clone, Copy)]
 pub struct Interval {
     pub min: f64,
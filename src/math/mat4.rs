@@ -0,0 +1,241 @@
+use std::ops;
+
+use crate::math::vec3::{Point3, Vec3};
+
+/// A row-major 4x4 matrix for affine transforms of points and vectors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Mat4::IDENTITY
+    }
+}
+
+impl Mat4 {
+    pub const IDENTITY: Mat4 = Mat4 {
+        m: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub fn new(m: [[f64; 4]; 4]) -> Self {
+        Mat4 { m }
+    }
+
+    pub fn translation(offset: Vec3) -> Self {
+        let mut result = Mat4::IDENTITY;
+        result.m[0][3] = offset.x;
+        result.m[1][3] = offset.y;
+        result.m[2][3] = offset.z;
+        result
+    }
+
+    pub fn scaling(factors: Vec3) -> Self {
+        let mut result = Mat4::IDENTITY;
+        result.m[0][0] = factors.x;
+        result.m[1][1] = factors.y;
+        result.m[2][2] = factors.z;
+        result
+    }
+
+    pub fn rotation_x(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Mat4::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, cos, -sin, 0.0],
+            [0.0, sin, cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_y(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Mat4::new([
+            [cos, 0.0, sin, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [-sin, 0.0, cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_z(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Mat4::new([
+            [cos, -sin, 0.0, 0.0],
+            [sin, cos, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        let mut result = Mat4::IDENTITY;
+        for (i, row) in result.m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        result
+    }
+
+    /// Inverts the matrix with Gauss-Jordan elimination and partial
+    /// pivoting. Returns `None` if the matrix is singular.
+    pub fn inverse(&self) -> Option<Mat4> {
+        let mut a = self.m;
+        let mut inv = Mat4::IDENTITY.m;
+
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+                .unwrap_or(col);
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for k in 0..4 {
+                a[col][k] *= scale;
+                inv[col][k] *= scale;
+            }
+
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let factor = a[row][col];
+                for k in 0..4 {
+                    a[row][k] -= factor * a[col][k];
+                    inv[row][k] -= factor * inv[col][k];
+                }
+            }
+        }
+
+        Some(Mat4::new(inv))
+    }
+
+    /// Transforms a point, applying the translation.
+    pub fn transform_point(&self, p: Point3) -> Point3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3],
+            m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3],
+            m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3],
+        )
+    }
+
+    /// Transforms a direction, ignoring the translation.
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+
+    /// Determinant of the upper-left 3x3 block, i.e. the volume scale of
+    /// the transform.
+    pub fn linear_determinant(&self) -> f64 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+}
+
+impl ops::Mul<Mat4> for Mat4 {
+    type Output = Mat4;
+    fn mul(self, rhs: Mat4) -> Self::Output {
+        let mut result = [[0.0; 4]; 4];
+        for (i, row) in result.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Mat4::new(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_mat_eq(a: &Mat4, b: &Mat4) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!(
+                    (a.m[i][j] - b.m[i][j]).abs() < 1e-9,
+                    "matrices differ at ({i}, {j}):\n{a:?}\n{b:?}"
+                );
+            }
+        }
+    }
+
+    fn assert_vec_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-9, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_translation_moves_points_not_vectors() {
+        let t = Mat4::translation(Vec3::new(1.0, 2.0, 3.0));
+        let v = Vec3::new(1.0, 1.0, 1.0);
+        assert_vec_close(t.transform_point(v), Vec3::new(2.0, 3.0, 4.0));
+        assert_vec_close(t.transform_vector(v), v);
+    }
+
+    #[test]
+    fn test_rotations() {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let y = Vec3::new(0.0, 1.0, 0.0);
+        let z = Vec3::new(0.0, 0.0, 1.0);
+        assert_vec_close(Mat4::rotation_z(90.0).transform_vector(x), y);
+        assert_vec_close(Mat4::rotation_x(90.0).transform_vector(y), z);
+        assert_vec_close(Mat4::rotation_y(90.0).transform_vector(z), x);
+    }
+
+    #[test]
+    fn test_composition_applies_right_to_left() {
+        let m =
+            Mat4::translation(Vec3::new(1.0, 0.0, 0.0)) * Mat4::scaling(Vec3::new(2.0, 2.0, 2.0));
+        assert_vec_close(
+            m.transform_point(Vec3::new(1.0, 1.0, 1.0)),
+            Vec3::new(3.0, 2.0, 2.0),
+        );
+    }
+
+    #[test]
+    fn test_inverse() {
+        let m = Mat4::translation(Vec3::new(1.0, -2.0, 3.0))
+            * Mat4::rotation_y(30.0)
+            * Mat4::rotation_x(-70.0)
+            * Mat4::scaling(Vec3::new(2.0, 0.5, 3.0));
+        let inv = m.inverse().expect("matrix should be invertible");
+
+        assert_mat_eq(&(m * inv), &Mat4::IDENTITY);
+        assert_mat_eq(&(inv * m), &Mat4::IDENTITY);
+        assert!(Mat4::scaling(Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+    }
+
+    #[test]
+    fn test_transpose() {
+        let m = Mat4::translation(Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(m.transpose().m[3], [1.0, 2.0, 3.0, 1.0]);
+        assert_mat_eq(&m.transpose().transpose(), &m);
+    }
+
+    #[test]
+    fn test_linear_determinant() {
+        let m = Mat4::translation(Vec3::new(4.0, 0.0, 0.0))
+            * Mat4::rotation_z(33.0)
+            * Mat4::scaling(Vec3::new(2.0, 3.0, -0.5));
+        assert!((m.linear_determinant() + 3.0).abs() < 1e-9);
+    }
+}
@@ -1,8 +1,16 @@
+pub mod aabb;
 pub mod interval;
+pub mod mat4;
+pub mod onb;
+pub mod perlin;
+pub mod polynomial;
 pub mod ray;
 pub mod utils;
 pub mod vec3;
 
+pub use aabb::Aabb;
 pub use interval::Interval;
+pub use mat4::Mat4;
+pub use onb::Onb;
 pub use ray::Ray;
 pub use vec3::{Color, Point3, Vec3};
@@ -0,0 +1,225 @@
+/// Real roots of `a x² + b x + c`, in ascending order. Uses the form that
+/// avoids cancellation between `b` and the square root of the
+/// discriminant, and falls back to the linear solution when `a` is zero.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Option<(f64, f64)> {
+    if a == 0.0 {
+        if b == 0.0 {
+            return None;
+        }
+        let x = -c / b;
+        return Some((x, x));
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let q = -0.5 * (b + discriminant.sqrt().copysign(b));
+    if q == 0.0 {
+        // b and c are both zero: a double root at 0.
+        return Some((0.0, 0.0));
+    }
+    let (x0, x1) = (q / a, c / q);
+    Some(if x0 <= x1 { (x0, x1) } else { (x1, x0) })
+}
+
+/// Up to four real roots, in ascending order.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Roots {
+    values: [f64; 4],
+    len: usize,
+}
+
+impl Roots {
+    fn push(&mut self, x: f64) {
+        if self.len < self.values.len() {
+            self.values[self.len] = x;
+            self.len += 1;
+        }
+    }
+
+    fn sort(&mut self) {
+        self.values[..self.len].sort_by(f64::total_cmp);
+    }
+
+    pub fn as_slice(&self) -> &[f64] {
+        &self.values[..self.len]
+    }
+}
+
+/// Real roots of `a x³ + b x² + c x + d` with `a` non-zero.
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Roots {
+    let (b, c, d) = (b / a, c / a, d / a);
+
+    // Depressed cubic y³ + p y + q with x = y - b/3.
+    let shift = b / 3.0;
+    let p = c - b * b / 3.0;
+    let q = 2.0 * b * b * b / 27.0 - b * c / 3.0 + d;
+
+    let mut roots = Roots::default();
+    let discriminant = (q / 2.0).powi(2) + (p / 3.0).powi(3);
+    if discriminant > 0.0 {
+        // One real root (Cardano).
+        let sqrt_disc = discriminant.sqrt();
+        let u = (-q / 2.0 + sqrt_disc).cbrt();
+        let v = (-q / 2.0 - sqrt_disc).cbrt();
+        roots.push(u + v - shift);
+    } else if p == 0.0 {
+        roots.push(-shift);
+    } else {
+        // Three real roots (trigonometric form).
+        let m = 2.0 * (-p / 3.0).sqrt();
+        let cos_arg = (3.0 * q / (p * m)).clamp(-1.0, 1.0);
+        let theta = cos_arg.acos() / 3.0;
+        for k in 0..3 {
+            let angle = theta - 2.0 * std::f64::consts::PI * k as f64 / 3.0;
+            roots.push(m * angle.cos() - shift);
+        }
+    }
+    roots.sort();
+    roots
+}
+
+/// Real roots of `a x⁴ + b x³ + c x² + d x + e` with `a` non-zero, by
+/// Ferrari's method. Each root is polished with Newton steps on the
+/// original polynomial, which recovers the precision the closed form
+/// loses when roots are close together or far apart in magnitude.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Roots {
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+
+    // Depressed quartic y⁴ + p y² + q y + r with x = y - b/4.
+    let shift = b / 4.0;
+    let b2 = b * b;
+    let p = c - 3.0 * b2 / 8.0;
+    let q = d - b * c / 2.0 + b2 * b / 8.0;
+    let r = e - b * d / 4.0 + b2 * c / 16.0 - 3.0 * b2 * b2 / 256.0;
+
+    let mut depressed = Roots::default();
+    let mut push_quadratic = |a: f64, b: f64, c: f64| {
+        if let Some((y0, y1)) = solve_quadratic(a, b, c) {
+            depressed.push(y0);
+            depressed.push(y1);
+        }
+    };
+
+    // The largest root m of the resolvent cubic splits the quartic into
+    // two quadratics in y.
+    let m = solve_cubic(8.0, 8.0 * p, 2.0 * p * p - 8.0 * r, -q * q)
+        .as_slice()
+        .last()
+        .copied()
+        .unwrap_or(0.0);
+
+    if m <= 1e-12 * (p.abs() + r.abs().sqrt()).max(1.0) {
+        // q is (nearly) zero: a quadratic in y².
+        if let Some((z0, z1)) = solve_quadratic(1.0, p, r) {
+            for z in [z0, z1] {
+                if z >= 0.0 {
+                    push_quadratic(1.0, 0.0, -z);
+                }
+            }
+        }
+    } else {
+        let s = (2.0 * m).sqrt();
+        push_quadratic(1.0, s, p / 2.0 + m - q / (2.0 * s));
+        push_quadratic(1.0, -s, p / 2.0 + m + q / (2.0 * s));
+    }
+
+    let mut roots = Roots::default();
+    for &y in depressed.as_slice() {
+        roots.push(polish_quartic(y - shift, [b, c, d, e]));
+    }
+    roots.sort();
+    roots
+}
+
+/// A few Newton steps on the monic quartic with the given lower
+/// coefficients, keeping the starting point if they do not help.
+fn polish_quartic(x: f64, [b, c, d, e]: [f64; 4]) -> f64 {
+    let eval = |x: f64| {
+        let value = (((x + b) * x + c) * x + d) * x + e;
+        let slope = ((4.0 * x + 3.0 * b) * x + 2.0 * c) * x + d;
+        (value, slope)
+    };
+
+    let mut best = x;
+    let (mut best_value, _) = eval(x);
+    let mut x = x;
+    for _ in 0..4 {
+        let (value, slope) = eval(x);
+        if value.abs() < best_value.abs() {
+            best = x;
+            best_value = value;
+        }
+        if slope == 0.0 || value == 0.0 {
+            break;
+        }
+        x -= value / slope;
+    }
+    if eval(x).0.abs() < best_value.abs() {
+        best = x;
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quadratic_roots() {
+        assert_eq!(solve_quadratic(1.0, -3.0, 2.0), Some((1.0, 2.0)));
+        assert_eq!(solve_quadratic(-2.0, 0.0, 8.0), Some((-2.0, 2.0)));
+        assert_eq!(solve_quadratic(0.0, 2.0, -4.0), Some((2.0, 2.0)));
+        assert_eq!(solve_quadratic(1.0, 0.0, 0.0), Some((0.0, 0.0)));
+        assert_eq!(solve_quadratic(1.0, 0.0, 1.0), None);
+    }
+
+    #[test]
+    fn test_quadratic_is_stable_for_small_roots() {
+        // Roots 1e-9 and 1e9: the naive formula loses the small one.
+        let (small, large) = solve_quadratic(1.0, -(1e9 + 1e-9), 1.0).unwrap();
+        assert!((small - 1e-9).abs() < 1e-20);
+        assert!((large - 1e9).abs() < 1e-3);
+    }
+
+    fn assert_roots(roots: Roots, expected: &[f64]) {
+        assert_eq!(roots.as_slice().len(), expected.len(), "{roots:?}");
+        for (root, want) in roots.as_slice().iter().zip(expected) {
+            assert!(
+                (root - want).abs() < 1e-9 * want.abs().max(1.0),
+                "{roots:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_cubic_roots() {
+        // (x - 1)(x - 2)(x + 3)
+        assert_roots(solve_cubic(1.0, 0.0, -7.0, 6.0), &[-3.0, 1.0, 2.0]);
+        // (x - 2)(x² + 1)
+        assert_roots(solve_cubic(2.0, -4.0, 2.0, -4.0), &[2.0]);
+    }
+
+    #[test]
+    fn test_quartic_roots() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert_roots(
+            solve_quartic(1.0, -10.0, 35.0, -50.0, 24.0),
+            &[1.0, 2.0, 3.0, 4.0],
+        );
+        // (x² - 4)(x² + 1): biquadratic with two real roots.
+        assert_roots(solve_quartic(1.0, 0.0, -3.0, 0.0, -4.0), &[-2.0, 2.0]);
+        // (x² + 1)(x² + 2): none.
+        assert_roots(solve_quartic(3.0, 0.0, 9.0, 0.0, 6.0), &[]);
+        // (x - 0.001)(x - 0.002)(x - 1000)(x + 5): widely spread roots.
+        let roots = [-5.0, 0.001, 0.002, 1000.0];
+        let [r0, r1, r2, r3] = roots;
+        let b = -(r0 + r1 + r2 + r3);
+        let c = r0 * r1 + r0 * r2 + r0 * r3 + r1 * r2 + r1 * r3 + r2 * r3;
+        let d = -(r0 * r1 * r2 + r0 * r1 * r3 + r0 * r2 * r3 + r1 * r2 * r3);
+        let e = r0 * r1 * r2 * r3;
+        assert_roots(solve_quartic(1.0, b, c, d, e), &roots);
+    }
+}
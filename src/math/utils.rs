@@ -14,3 +14,31 @@ pub fn random() -> f64 {
 pub fn random_range(min: f64, max: f64) -> f64 {
     ORACLE.with(|rng| rng.borrow_mut().random_range(min..max))
 }
+
+/// Restarts this thread's generator from `seed`. Everything drawn on the
+/// thread afterwards is a pure function of the seed.
+pub fn reseed(seed: u64) {
+    ORACLE.with(|rng| *rng.borrow_mut() = SmallRng::seed_from_u64(seed));
+}
+
+/// Derives the seed of an independent stream, e.g. one per
+/// `(seed, pixel, sample)`, so results do not depend on which thread (or in
+/// which order) the work runs.
+pub fn stream_seed(seed: u64, indices: &[u64]) -> u64 {
+    indices
+        .iter()
+        .fold(splitmix64(seed), |hash, &index| splitmix64(hash ^ index))
+}
+
+/// Returns a random seed from the operating system.
+pub fn random_seed() -> u64 {
+    SmallRng::from_os_rng().random()
+}
+
+/// Sebastiano Vigna's SplitMix64 finalizer: a cheap, well-mixed hash.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
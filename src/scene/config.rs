@@ -1,4 +1,9 @@
-use crate::math::vec3::{Color, Vec3};
+use std::collections::BTreeMap;
+
+use crate::{
+    imaging::{filter::FilterType, sampler::SamplerType, tonemap::ToneMapOperator},
+    math::vec3::{Color, Vec3},
+};
 use serde::Deserialize;
 
 #[derive(Deserialize)]
@@ -6,8 +11,20 @@ pub struct SceneConfig {
     pub aspect_ratio: f64,
     pub camera: CameraConfig,
     pub objects: Vec<ObjectConfig>,
+    /// Named groups of objects that are built once and placed with
+    /// `Instance` objects.
+    #[serde(default)]
+    pub definitions: BTreeMap<String, Vec<ObjectConfig>>,
     #[serde(default = "default_background")]
     pub background: Background,
+    #[serde(default)]
+    pub tonemap: ToneMapConfig,
+    #[serde(default)]
+    pub sampler: SamplerType,
+    #[serde(default)]
+    pub filter: FilterConfig,
+    #[serde(default)]
+    pub atmosphere: Option<AtmosphereConfig>,
 }
 
 fn default_background() -> Background {
@@ -17,6 +34,18 @@ fn default_background() -> Background {
     }
 }
 
+fn default_texture_scale() -> f64 {
+    1.0
+}
+
+fn default_caps() -> bool {
+    true
+}
+
+fn default_torus_axis() -> Vec3 {
+    Vec3::new(0.0, 1.0, 0.0)
+}
+
 #[derive(Deserialize)]
 pub struct CameraConfig {
     pub look_from: Vec3,
@@ -25,6 +54,77 @@ pub struct CameraConfig {
     pub fov: f64,
     pub defocus_angle: f64,
     pub focus_dist: f64,
+    /// Re-aims the camera so the whole scene fits in view, keeping the
+    /// direction from `look_at` to `look_from`.
+    #[serde(default)]
+    pub auto_frame: bool,
+}
+
+/// How the render is mapped to 8-bit output. Command-line flags override
+/// these values.
+#[derive(Deserialize, Default)]
+pub struct ToneMapConfig {
+    #[serde(default)]
+    pub operator: ToneMapOperator,
+    /// Exposure in stops.
+    #[serde(default)]
+    pub exposure: f64,
+    #[serde(default)]
+    pub white_point: Option<f64>,
+}
+
+/// Pixel reconstruction filter. Command-line flags override these values.
+#[derive(Deserialize, Default)]
+pub struct FilterConfig {
+    #[serde(rename = "type", default)]
+    pub kind: FilterType,
+    /// Radius in pixels; each filter type has its own default.
+    #[serde(default)]
+    pub radius: Option<f64>,
+}
+
+/// Fog filling the whole scene, scattering light along every ray.
+#[derive(Deserialize)]
+pub struct AtmosphereConfig {
+    #[serde(rename = "type", default)]
+    pub kind: AtmosphereType,
+    /// Density everywhere, or at `base_height` for height fog.
+    pub density: f64,
+    /// Height fog only: where the fog has `density` (default 0).
+    #[serde(default)]
+    pub base_height: Option<f64>,
+    /// Height fog only: how fast the density drops per unit of height.
+    #[serde(default)]
+    pub falloff: Option<f64>,
+    #[serde(default = "default_atmosphere_albedo")]
+    pub albedo: Color,
+    /// Henyey–Greenstein asymmetry; 0 scatters evenly in all directions.
+    #[serde(default)]
+    pub g: f64,
+    /// The world-space box the fog fills. Homogeneous fog defaults to the
+    /// box around the scene and the camera, height fog to no limit.
+    #[serde(default)]
+    pub bounds: Option<BoundsConfig>,
+}
+
+/// An axis-aligned box given by two opposite corners.
+#[derive(Deserialize)]
+pub struct BoundsConfig {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AtmosphereType {
+    /// The same density everywhere.
+    #[default]
+    Homogeneous,
+    /// Density falling off exponentially with height.
+    HeightFog,
+}
+
+fn default_atmosphere_albedo() -> Color {
+    Color::new(1.0, 1.0, 1.0)
 }
 
 #[derive(Deserialize)]
@@ -34,21 +134,224 @@ pub enum ObjectConfig {
         center: Vec3,
         radius: f64,
         material: MaterialConfig,
+        #[serde(default)]
+        transform: Option<TransformConfig>,
     },
     Triangle {
         v0: Vec3,
         v1: Vec3,
         v2: Vec3,
+        /// Optional per-vertex normals for smooth shading; give all or none.
+        #[serde(default)]
+        n0: Option<Vec3>,
+        #[serde(default)]
+        n1: Option<Vec3>,
+        #[serde(default)]
+        n2: Option<Vec3>,
+        material: MaterialConfig,
+        #[serde(default)]
+        transform: Option<TransformConfig>,
+    },
+    /// Parallelogram spanned by the edges `u` and `v` from `corner`.
+    Quad {
+        corner: Vec3,
+        u: Vec3,
+        v: Vec3,
+        material: MaterialConfig,
+        #[serde(default)]
+        transform: Option<TransformConfig>,
+    },
+    /// Infinite plane through `point`, facing `normal`.
+    Plane {
+        point: Vec3,
+        normal: Vec3,
+        /// World-space size of one tile of texture coordinates.
+        #[serde(default = "default_texture_scale")]
+        texture_scale: f64,
+        material: MaterialConfig,
+        #[serde(default)]
+        transform: Option<TransformConfig>,
+    },
+    Disk {
+        center: Vec3,
+        normal: Vec3,
+        radius: f64,
+        material: MaterialConfig,
+        #[serde(default)]
+        transform: Option<TransformConfig>,
+    },
+    /// Cylinder around the segment from `base` to `base + axis`.
+    Cylinder {
+        base: Vec3,
+        axis: Vec3,
+        radius: f64,
+        /// Closes both ends with disks.
+        #[serde(default = "default_caps")]
+        caps: bool,
+        material: MaterialConfig,
+        #[serde(default)]
+        transform: Option<TransformConfig>,
+    },
+    /// Cone from `radius` at `base` to `top_radius` at `base + axis`; a
+    /// non-zero `top_radius` truncates it.
+    Cone {
+        base: Vec3,
+        axis: Vec3,
+        radius: f64,
+        #[serde(default)]
+        top_radius: f64,
+        #[serde(default = "default_caps")]
+        caps: bool,
+        material: MaterialConfig,
+        #[serde(default)]
+        transform: Option<TransformConfig>,
+    },
+    /// Cylinder from `base` to `base + axis` with hemispherical ends.
+    Capsule {
+        base: Vec3,
+        axis: Vec3,
+        radius: f64,
+        material: MaterialConfig,
+        #[serde(default)]
+        transform: Option<TransformConfig>,
+    },
+    /// Ring of tube radius `minor_radius` around a circle of `major_radius`,
+    /// facing `axis` (up by default).
+    Torus {
+        center: Vec3,
+        #[serde(default = "default_torus_axis")]
+        axis: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
         material: MaterialConfig,
+        #[serde(default)]
+        transform: Option<TransformConfig>,
+    },
+    /// Axis-aligned box between two corners, made of six quads.
+    Box {
+        min: Vec3,
+        max: Vec3,
+        material: MaterialConfig,
+        #[serde(default)]
+        transform: Option<TransformConfig>,
+    },
+    /// Wavefront OBJ file, relative to the scene file's directory.
+    Mesh {
+        path: String,
+        material: MaterialConfig,
+        #[serde(default)]
+        transform: Option<TransformConfig>,
+    },
+    /// Fog or smoke of uniform `density` filling a closed `boundary` object.
+    /// The boundary lends only its shape, so its own material is not used;
+    /// `material` is the volume's phase function.
+    Volume {
+        boundary: Box<ObjectConfig>,
+        density: f64,
+        material: MaterialConfig,
+    },
+    /// Places a shared copy of one of the scene's `definitions`.
+    Instance {
+        of: String,
+        #[serde(default)]
+        transform: Option<TransformConfig>,
     },
 }
 
-#[derive(Deserialize)]
+/// Applied as scale, then rotation (degrees about x, then y, then z), then
+/// translation.
+#[derive(Deserialize, Clone, Copy)]
+pub struct TransformConfig {
+    #[serde(default)]
+    pub translate: Vec3,
+    #[serde(default)]
+    pub rotate: Vec3,
+    #[serde(default)]
+    pub scale: ScaleConfig,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(untagged)]
+pub enum ScaleConfig {
+    Uniform(f64),
+    PerAxis(Vec3),
+}
+
+impl Default for ScaleConfig {
+    fn default() -> Self {
+        ScaleConfig::Uniform(1.0)
+    }
+}
+
+impl ScaleConfig {
+    pub fn to_vec3(self) -> Vec3 {
+        match self {
+            ScaleConfig::Uniform(s) => Vec3::new(s, s, s),
+            ScaleConfig::PerAxis(v) => v,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, PartialEq)]
 #[serde(tag = "type")]
 pub enum MaterialConfig {
-    Lambertian { albedo: Color },
-    Metal { albedo: Color, fuzz: f64 },
-    Dielectric { index: f64 },
+    Lambertian {
+        albedo: TextureSource,
+    },
+    Metal {
+        albedo: TextureSource,
+        fuzz: f64,
+    },
+    Dielectric {
+        index: f64,
+    },
+    DiffuseLight {
+        emit: TextureSource,
+    },
+    /// Phase function for volumes that scatters equally in all directions.
+    Isotropic {
+        albedo: TextureSource,
+    },
+    /// Phase function for volumes, skewed forwards by `g` in (-1, 1), or
+    /// backwards when `g` is negative.
+    HenyeyGreenstein {
+        albedo: TextureSource,
+        g: f64,
+    },
+}
+
+/// Either a plain `{ x, y, z }` color or a `type`-tagged texture.
+#[derive(Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum TextureSource {
+    Color(Color),
+    Texture(TextureConfig),
+}
+
+#[derive(Deserialize, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum TextureConfig {
+    Solid {
+        color: Color,
+    },
+    Checker {
+        scale: f64,
+        even: Box<TextureSource>,
+        odd: Box<TextureSource>,
+    },
+    /// Image file, relative to the scene file's directory.
+    Image {
+        path: String,
+    },
+    Noise {
+        scale: f64,
+        #[serde(default = "default_noise_color")]
+        color: Color,
+    },
+}
+
+fn default_noise_color() -> Color {
+    Color::new(1.0, 1.0, 1.0)
 }
 
 #[derive(Deserialize, Clone)]
@@ -0,0 +1,117 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use crate::scene::obj::ObjError;
+
+/// Error raised while loading a scene file, pointing at where it went wrong.
+#[derive(Debug)]
+pub struct SceneError {
+    /// The scene file being loaded.
+    pub path: PathBuf,
+    /// Position in the scene file, when known.
+    pub location: Option<Location>,
+    /// Which part of the scene is at fault, e.g. `objects[2]` or `camera`.
+    pub context: Option<String>,
+    pub kind: SceneErrorKind,
+}
+
+/// 1-based line and column in a scene file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug)]
+pub enum SceneErrorKind {
+    Io(io::Error),
+    /// Malformed YAML or a value that does not match the scene schema.
+    Syntax(String),
+    /// Well-formed YAML describing something that cannot be rendered.
+    Invalid(String),
+    Mesh {
+        path: PathBuf,
+        source: Box<ObjError>,
+    },
+    Texture {
+        path: PathBuf,
+        source: Box<image::ImageError>,
+    },
+}
+
+impl SceneError {
+    pub fn new(path: &Path, kind: SceneErrorKind) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            location: None,
+            context: None,
+            kind,
+        }
+    }
+
+    pub fn with_context(mut self, context: impl Into<String>) -> Self {
+        self.context = Some(context.into());
+        self
+    }
+
+    /// Wraps a YAML error, keeping its location separately from the message.
+    pub fn from_yaml(path: &Path, err: serde_yaml::Error) -> Self {
+        let message = err.to_string();
+        let Some(location) = err.location() else {
+            return SceneError::new(path, SceneErrorKind::Syntax(message));
+        };
+
+        let suffix = format!(" at line {} column {}", location.line(), location.column());
+        let message = message
+            .strip_suffix(&suffix)
+            .unwrap_or(&message)
+            .to_string();
+
+        Self {
+            location: Some(Location {
+                line: location.line(),
+                column: location.column(),
+            }),
+            ..SceneError::new(path, SceneErrorKind::Syntax(message))
+        }
+    }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(Location { line, column }) = self.location {
+            write!(f, ":{line}:{column}")?;
+        }
+        write!(f, ": ")?;
+        if let Some(context) = &self.context {
+            write!(f, "{context}: ")?;
+        }
+
+        match &self.kind {
+            SceneErrorKind::Io(err) => write!(f, "{err}"),
+            SceneErrorKind::Syntax(message) | SceneErrorKind::Invalid(message) => {
+                write!(f, "{message}")
+            }
+            SceneErrorKind::Mesh { path, source } => {
+                write!(f, "unable to load mesh {}: {source}", path.display())
+            }
+            SceneErrorKind::Texture { path, source } => {
+                write!(f, "unable to load texture {}: {source}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            SceneErrorKind::Io(err) => Some(err),
+            SceneErrorKind::Mesh { source, .. } => Some(source.as_ref()),
+            SceneErrorKind::Texture { source, .. } => Some(source.as_ref()),
+            SceneErrorKind::Syntax(_) | SceneErrorKind::Invalid(_) => None,
+        }
+    }
+}
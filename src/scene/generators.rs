@@ -1,5 +1,5 @@
 use crate::{
-    geometry::{hittable_list::HittableList, sphere::Sphere},
+    geometry::{hittable_list::HittableList, plane::Plane, sphere::Sphere},
     imaging::{
         camera::Camera,
         material::{Dielectric, Lambertian, MaterialKind, Metal},
@@ -11,13 +11,15 @@ use crate::{
 };
 
 /// Generates a random scene similar to the cover of "Ray Tracing in One Weekend".
-pub fn random_book_scene(aspect_ratio: f64) -> (HittableList, Camera) {
+/// The same seed always produces the same scene.
+pub fn random_book_scene(aspect_ratio: f64, seed: u64) -> (HittableList, Camera) {
+    utils::reseed(seed);
     let mut world = HittableList::default();
 
     let ground_material = MaterialKind::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
-    world.add(Sphere::new(
-        Vec3::new(0.0, -1000.0, 0.0),
-        1000.0,
+    world.add(Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
         ground_material,
     ));
 
@@ -1,5 +1,13 @@
 pub mod config;
+pub mod error;
 pub mod generators;
+pub mod obj;
 
-pub use config::{Background, CameraConfig, MaterialConfig, ObjectConfig, SceneConfig};
+pub use config::{
+    AtmosphereConfig, AtmosphereType, Background, BoundsConfig, CameraConfig, FilterConfig,
+    MaterialConfig, ObjectConfig, ScaleConfig, SceneConfig, TextureConfig, TextureSource,
+    ToneMapConfig, TransformConfig,
+};
+pub use error::{Location, SceneError, SceneErrorKind};
 pub use generators::random_book_scene;
+pub use obj::{ObjError, load_obj};
@@ -0,0 +1,260 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use crate::{
+    geometry::mesh::{MeshFace, TriangleMesh},
+    imaging::material::MaterialKind,
+    math::vec3::Vec3,
+};
+
+/// Error raised while reading a Wavefront OBJ file.
+#[derive(Debug)]
+pub enum ObjError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(err) => write!(f, "{err}"),
+            ObjError::Parse { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+impl From<io::Error> for ObjError {
+    fn from(err: io::Error) -> Self {
+        ObjError::Io(err)
+    }
+}
+
+/// Loads an OBJ file into a mesh using `material` for every face.
+pub fn load_obj<P: AsRef<Path>>(path: P, material: MaterialKind) -> Result<TriangleMesh, ObjError> {
+    let file = File::open(path)?;
+    parse_obj(BufReader::new(file), material)
+}
+
+/// Parses vertices (`v`), normals (`vn`), texture coordinates (`vt`) and
+/// faces (`f`). Polygons are fan-triangulated; other statements are ignored.
+pub fn parse_obj<R: BufRead>(reader: R, material: MaterialKind) -> Result<TriangleMesh, ObjError> {
+    let mut mesh = TriangleMesh {
+        positions: Vec::new(),
+        normals: Vec::new(),
+        uvs: Vec::new(),
+        faces: Vec::new(),
+        material,
+    };
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_number = index + 1;
+        let error = |message: String| ObjError::Parse {
+            line: line_number,
+            message,
+        };
+
+        let line = line.split('#').next().unwrap_or("");
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => mesh.positions.push(parse_vec3(&args).map_err(error)?),
+            "vn" => {
+                let normal = parse_vec3(&args).map_err(error)?;
+                if normal.length_squared() == 0.0 {
+                    return Err(error("normal has zero length".to_string()));
+                }
+                mesh.normals.push(normal.unit_vector());
+            }
+            "vt" => {
+                let u = parse_float(args.first().copied()).map_err(error)?;
+                let v = args.get(1).map_or(Ok(0.0), |s| parse_float(Some(s)));
+                mesh.uvs.push((u, v.map_err(error)?));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(error(format!(
+                        "face needs at least 3 vertices, found {}",
+                        args.len()
+                    )));
+                }
+
+                let corners = args
+                    .iter()
+                    .map(|corner| parse_corner(corner, &mesh))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(error)?;
+
+                for i in 1..corners.len() - 1 {
+                    let (a, b, c) = (corners[0], corners[i], corners[i + 1]);
+                    mesh.faces.push(MeshFace {
+                        positions: [a.0, b.0, c.0],
+                        uvs: a.1.zip(b.1).zip(c.1).map(|((a, b), c)| [a, b, c]),
+                        normals: a.2.zip(b.2).zip(c.2).map(|((a, b), c)| [a, b, c]),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(mesh)
+}
+
+fn parse_float(token: Option<&str>) -> Result<f64, String> {
+    let token = token.ok_or_else(|| "missing number".to_string())?;
+    token
+        .parse()
+        .map_err(|_| format!("invalid number '{token}'"))
+}
+
+fn parse_vec3(args: &[&str]) -> Result<Vec3, String> {
+    Ok(Vec3::new(
+        parse_float(args.first().copied())?,
+        parse_float(args.get(1).copied())?,
+        parse_float(args.get(2).copied())?,
+    ))
+}
+
+type Corner = (usize, Option<usize>, Option<usize>);
+
+/// Parses a `v`, `v/vt`, `v//vn` or `v/vt/vn` face corner into zero-based
+/// indices. Negative indices count back from the latest element.
+fn parse_corner(corner: &str, mesh: &TriangleMesh) -> Result<Corner, String> {
+    let mut parts = corner.split('/');
+
+    let position = resolve_index(parts.next(), mesh.positions.len(), "vertex")?
+        .ok_or_else(|| format!("missing vertex index in '{corner}'"))?;
+    let uv = resolve_index(parts.next(), mesh.uvs.len(), "texture coordinate")?;
+    let normal = resolve_index(parts.next(), mesh.normals.len(), "normal")?;
+
+    Ok((position, uv, normal))
+}
+
+fn resolve_index(token: Option<&str>, count: usize, kind: &str) -> Result<Option<usize>, String> {
+    let Some(token) = token.filter(|t| !t.is_empty()) else {
+        return Ok(None);
+    };
+
+    let index: i64 = token
+        .parse()
+        .map_err(|_| format!("invalid {kind} index '{token}'"))?;
+
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        count as i64 + index
+    };
+
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!("{kind} index {index} out of range (have {count})"));
+    }
+
+    Ok(Some(resolved as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::imaging::material::Lambertian;
+    use crate::math::vec3::Color;
+    use std::io::Cursor;
+
+    fn parse(source: &str) -> Result<TriangleMesh, ObjError> {
+        let material = MaterialKind::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        parse_obj(Cursor::new(source), material)
+    }
+
+    #[test]
+    fn test_parse_triangle() {
+        let mesh = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        assert_eq!(mesh.positions.len(), 3);
+        assert_eq!(mesh.faces.len(), 1);
+        assert_eq!(mesh.faces[0].positions, [0, 1, 2]);
+        assert_eq!(mesh.faces[0].normals, None);
+        assert_eq!(mesh.faces[0].uvs, None);
+    }
+
+    #[test]
+    fn test_fan_triangulates_polygons() {
+        let mesh = parse(
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv -1 0.5 0\n\
+             f 1 2 3 4 5\n",
+        )
+        .unwrap();
+        let faces: Vec<[usize; 3]> = mesh.faces.iter().map(|f| f.positions).collect();
+        assert_eq!(faces, vec![[0, 1, 2], [0, 2, 3], [0, 3, 4]]);
+    }
+
+    #[test]
+    fn test_parse_normals_uvs_and_comments() {
+        let mesh = parse(
+            "# a quad\n\
+             o quad\n\
+             v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+             vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n\
+             vn 0 0 2\n\
+             s off\n\
+             f 1/1/1 2/2/1 3/3/1 4/4/1 # trailing comment\n\
+             f 1//1 2//1 3//1\n",
+        )
+        .unwrap();
+
+        assert_eq!(mesh.faces.len(), 3);
+        assert_eq!(mesh.faces[0].uvs, Some([0, 1, 2]));
+        assert_eq!(mesh.faces[1].uvs, Some([0, 2, 3]));
+        assert_eq!(mesh.faces[0].normals, Some([0, 0, 0]));
+        assert_eq!(mesh.faces[2].uvs, None);
+        assert_eq!(mesh.faces[2].normals, Some([0, 0, 0]));
+        assert_eq!(mesh.normals[0], Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_negative_indices() {
+        let mesh = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\n").unwrap();
+        assert_eq!(mesh.faces[0].positions, [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_errors_report_line() {
+        match parse("v 0 0 0\nv 1 0 0\nf 1 2 3\n") {
+            Err(ObjError::Parse { line, .. }) => assert_eq!(line, 3),
+            other => panic!(
+                "expected parse error, got {:?}",
+                other.map(|m| m.faces.len())
+            ),
+        }
+        assert!(matches!(
+            parse("v 0 zero 0\n"),
+            Err(ObjError::Parse { line: 1, .. })
+        ));
+        assert!(matches!(
+            parse("v 0 0 0\nf 1 1\n"),
+            Err(ObjError::Parse { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_zero_normal_is_rejected() {
+        match parse("v 0 0 0\nvn 0 0 1\nvn 0 0 0\n") {
+            Err(ObjError::Parse { line, message }) => {
+                assert_eq!(line, 3);
+                assert!(message.contains("zero length"), "{message}");
+            }
+            other => panic!(
+                "expected parse error, got {:?}",
+                other.map(|m| m.normals.len())
+            ),
+        }
+    }
+}